@@ -0,0 +1,69 @@
+// fixtures/mod.rs: Representative hands shared by the benchmarks (and usable from tests via #[path])
+
+#![allow(dead_code)]
+
+use riichi_mahjong_scoring_calculator::implements::{
+    game::{AgariType, GameContext, PlayerContext},
+    input::UserInput,
+    parse_tile, parse_tiles,
+    tiles::{Kaze, tile_to_index},
+};
+
+/// (name, all 14 tiles, winning tile, tsumo)
+pub const HANDS: &[(&str, &str, &str, bool)] = &[
+    ("tanyao_pinfu", "234567m345678p44s", "8p", true),
+    ("chiitoitsu", "1155m2277p3399s11z", "1z", false),
+    ("kokushi", "119m19p19s1234567z", "1m", false),
+    ("chuuren", "11123455678999m", "5m", true),
+    // Many overlapping decompositions, stresses the recursive parser
+    ("pathological", "11122233344455m", "5m", false),
+];
+
+/// Builds a closed hand from notation. On Ron the winning tile is taken out of hand_tiles.
+pub fn hand(tiles: &str, win: &str, tsumo: bool) -> UserInput {
+    let mut hand_tiles = parse_tiles(tiles).unwrap();
+    let winning_tile = parse_tile(win).unwrap();
+    if !tsumo {
+        let pos = hand_tiles.iter().position(|t| *t == winning_tile).unwrap();
+        hand_tiles.remove(pos);
+    }
+
+    UserInput {
+        hand_tiles,
+        winning_tile,
+        open_melds: Vec::new(),
+        closed_kans: Vec::new(),
+        player_context: PlayerContext {
+            jikaze: Kaze::Nan,
+            is_oya: false,
+            is_riichi: false,
+            is_daburu_riichi: false,
+            is_open_riichi: false,
+            is_ippatsu: false,
+            is_menzen: true,
+        },
+        game_context: GameContext::with_dora(parse_tiles("7z").unwrap()),
+        agari_type: if tsumo {
+            AgariType::Tsumo
+        } else {
+            AgariType::Ron
+        },
+    }
+}
+
+pub fn all_hands() -> Vec<(&'static str, UserInput)> {
+    HANDS
+        .iter()
+        .map(|&(name, tiles, win, tsumo)| (name, hand(tiles, win, tsumo)))
+        .collect()
+}
+
+/// Tile counts of the 13 tiles before the winning tile, for shanten/waits
+pub fn tenpai_counts(tiles: &str, win: &str) -> [u8; 34] {
+    let mut counts = [0u8; 34];
+    for tile in parse_tiles(tiles).unwrap() {
+        counts[tile_to_index(&tile)] += 1;
+    }
+    counts[tile_to_index(&parse_tile(win).unwrap())] -= 1;
+    counts
+}
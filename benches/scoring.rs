@@ -0,0 +1,51 @@
+// scoring.rs: Criterion benchmarks for the scoring pipeline
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use riichi_mahjong_scoring_calculator::implements::{
+    calculate_agari, organize_hand, shanten, waits,
+};
+
+mod fixtures;
+
+fn bench_organize_hand(c: &mut Criterion) {
+    let mut group = c.benchmark_group("organize_hand");
+    for (name, input) in fixtures::all_hands() {
+        group.bench_function(name, |b| b.iter(|| organize_hand(black_box(&input))));
+    }
+    group.finish();
+}
+
+fn bench_calculate_agari(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_agari");
+    for (name, input) in fixtures::all_hands() {
+        group.bench_function(name, |b| b.iter(|| calculate_agari(black_box(&input))));
+    }
+    group.finish();
+}
+
+fn bench_shanten(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shanten");
+    for &(name, tiles, win, _) in fixtures::HANDS {
+        let counts = fixtures::tenpai_counts(tiles, win);
+        group.bench_function(name, |b| b.iter(|| shanten(black_box(&counts))));
+    }
+    group.finish();
+}
+
+fn bench_waits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("waits");
+    for &(name, tiles, win, _) in fixtures::HANDS {
+        let counts = fixtures::tenpai_counts(tiles, win);
+        group.bench_function(name, |b| b.iter(|| waits(black_box(&counts))));
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_organize_hand,
+    bench_calculate_agari,
+    bench_shanten,
+    bench_waits
+);
+criterion_main!(benches);
@@ -0,0 +1,142 @@
+// repl.rs: Type a hand and flags, see the score
+//
+//   cargo run --example repl
+//   > 234567m345678p44s win=8p tsumo riichi dora=2p
+//
+// The hand lists all 14 tiles, including the winning tile.
+// Flags: win=<tile> tsumo|ron riichi ippatsu dora=<tiles> ura=<tiles>
+//        seat=<1-4z> round=<1-4z> honba=<n>
+// Type "quit" to exit.
+
+use riichi_mahjong_scoring_calculator::implements::{
+    calculate_agari,
+    game::{AgariType, GameContext, PlayerContext},
+    input::UserInput,
+    parse_tile, parse_tiles,
+    tiles::{Hai, Jihai, Kaze},
+};
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    run(io::stdin().lock(), io::stdout()).ok();
+}
+
+/// Reads commands until EOF or "quit", writing one result line per command
+fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "quit" {
+            break;
+        }
+
+        match parse_command(line).and_then(|input| calculate_agari(&input)) {
+            Ok(result) => writeln!(output, "{}", result)?,
+            Err(e) => writeln!(output, "Error: {}", e)?,
+        }
+    }
+    Ok(())
+}
+
+fn parse_command(line: &str) -> Result<UserInput, &'static str> {
+    let mut words = line.split_whitespace();
+    let mut hand_tiles = parse_tiles(words.next().ok_or("Missing hand.")?)?;
+
+    let mut winning_tile = None;
+    let mut agari_type = AgariType::Ron;
+    let mut jikaze = Kaze::Nan;
+    let mut bakaze = Kaze::Ton;
+    let mut is_riichi = false;
+    let mut is_ippatsu = false;
+    let mut honba = 0;
+    let mut dora_indicators = Vec::new();
+    let mut uradora_indicators = Vec::new();
+
+    for word in words {
+        match word.split_once('=') {
+            Some(("win", tile)) => winning_tile = Some(parse_tile(tile)?),
+            Some(("dora", tiles)) => dora_indicators = parse_tiles(tiles)?,
+            Some(("ura", tiles)) => uradora_indicators = parse_tiles(tiles)?,
+            Some(("seat", tile)) => jikaze = parse_kaze(tile)?,
+            Some(("round", tile)) => bakaze = parse_kaze(tile)?,
+            Some(("honba", n)) => honba = n.parse().map_err(|_| "Invalid honba.")?,
+            None if word == "tsumo" => agari_type = AgariType::Tsumo,
+            None if word == "ron" => agari_type = AgariType::Ron,
+            None if word == "riichi" => is_riichi = true,
+            None if word == "ippatsu" => is_ippatsu = true,
+            _ => return Err("Unknown flag."),
+        }
+    }
+
+    let winning_tile = winning_tile.ok_or("Missing win=<tile>.")?;
+
+    // If Ron, the winning tile is not in hand_tiles
+    if agari_type == AgariType::Ron {
+        let pos = hand_tiles
+            .iter()
+            .position(|t| *t == winning_tile)
+            .ok_or("Winning tile is not in the hand.")?;
+        hand_tiles.remove(pos);
+    }
+
+    Ok(UserInput {
+        hand_tiles,
+        winning_tile,
+        open_melds: Vec::new(),
+        closed_kans: Vec::new(),
+        player_context: PlayerContext {
+            jikaze,
+            is_oya: jikaze == Kaze::Ton,
+            is_riichi,
+            is_daburu_riichi: false,
+            is_open_riichi: false,
+            is_ippatsu,
+            is_menzen: true,
+        },
+        game_context: GameContext {
+            bakaze,
+            honba,
+            uradora_indicators,
+            ..GameContext::with_dora(dora_indicators)
+        },
+        agari_type,
+    })
+}
+
+fn parse_kaze(notation: &str) -> Result<Kaze, &'static str> {
+    match parse_tile(notation)? {
+        Hai::Jihai(Jihai::Kaze(kaze)) => Ok(kaze),
+        _ => Err("Winds are 1z-4z."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+
+    #[test]
+    fn scripted_session() {
+        let script = "234567m345678p44s win=8p tsumo riichi dora=1z\n\
+                      234567m345678p44s\n\
+                      quit\n\
+                      234567m345678p44s win=8p\n";
+        let mut output = Vec::new();
+        run(script.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let results: Vec<&str> = output.split("> ").filter(|s| !s.is_empty()).collect();
+        assert_eq!(results.len(), 2, "stops at quit: {output}");
+        assert!(!results[0].starts_with("Error"), "{}", results[0]);
+        assert!(results[0].contains("Riichi"), "{}", results[0]);
+        assert_eq!(results[1], "Error: Missing win=<tile>.\n");
+    }
+}
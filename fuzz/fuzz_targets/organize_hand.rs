@@ -0,0 +1,10 @@
+#![no_main]
+
+// organize_hand must return Ok or Err for any input, never panic
+
+use libfuzzer_sys::fuzz_target;
+use riichi_mahjong_scoring_calculator::implements::{input::UserInput, organize_hand};
+
+fuzz_target!(|input: UserInput| {
+    let _ = organize_hand(&input);
+});
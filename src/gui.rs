@@ -1,9 +1,10 @@
 // gui.rs: Iced GUI
 
-use crate::implements::calculate_agari;
-use crate::implements::game::{AgariType, GameContext, PlayerContext};
+use crate::implements::calculate_agari_with_rules;
+use crate::implements::game::{AgariType, GameContext, PlayerContext, SpecialWin, SpecialWinFlags};
 use crate::implements::hand::MentsuType;
 use crate::implements::input::{OpenMeldInput, UserInput};
+use crate::implements::rules::Ruleset;
 use crate::implements::tiles::{Hai, Jihai, Kaze, Sangenpai, Suhai};
 use iced::widget::{button, checkbox, column, container, image, radio, row, scrollable, text};
 use iced::{Color, Element, Length, Sandbox, Settings, theme};
@@ -191,6 +192,7 @@ impl Sandbox for RiichiGui {
                     let meld = OpenMeldInput {
                         mentsu_type: m_type,
                         representative_tile: tile,
+                        called_from: None,
                     };
                     if let Some(idx) = editing_idx {
                         if idx < self.open_melds.len() {
@@ -354,46 +356,53 @@ impl Sandbox for RiichiGui {
                         }
                     }
 
-                    let input = UserInput {
-                        hand_tiles,
-                        open_melds: self.open_melds.clone(),
-                        closed_kans: self.closed_kans.clone(),
-                        winning_tile,
-                        agari_type: self.agari_type,
-                        player_context: PlayerContext {
-                            jikaze: self.jikaze,
-                            is_oya: self.jikaze == Kaze::Ton,
-                            is_riichi: self.is_riichi,
-                            is_daburu_riichi: self.is_daburu_riichi,
-                            is_ippatsu: self.is_ippatsu,
-                            is_menzen: self.open_melds.is_empty(),
-                        },
-                        game_context: GameContext {
-                            bakaze: self.bakaze,
-                            kyoku: 1,
-                            honba: self.honba,
-                            riichi_bou: if self.is_riichi { 1 } else { 0 },
-                            dora_indicators: self.dora_indicators.clone(),
-                            uradora_indicators: self.uradora_indicators.clone(),
-                            num_akadora: 0,
-                            is_tenhou: self.is_tenhou,
-                            is_chiihou: self.is_chiihou,
-                            is_renhou: self.is_renhou,
-                            is_haitei: self.is_haitei,
-                            is_houtei: self.is_houtei,
-                            is_rinshan: self.is_rinshan,
-                            is_chankan: self.is_chankan,
-                        },
+                    // The checkboxes allow several conditions; only one can apply
+                    let flags = SpecialWinFlags {
+                        is_tenhou: self.is_tenhou,
+                        is_chiihou: self.is_chiihou,
+                        is_renhou: self.is_renhou,
+                        is_haitei: self.is_haitei,
+                        is_houtei: self.is_houtei,
+                        is_rinshan: self.is_rinshan,
+                        is_chankan: self.is_chankan,
                     };
-                    // Calculate score
-                    match calculate_agari(&input) {
-                        Ok(result) => {
-                            self.score_result = Some(Ok(result));
-                        }
-                        Err(e) => {
-                            self.score_result = Some(Err(format!("Error: {}", e)));
-                        }
-                    }
+                    let result = SpecialWin::try_from(flags).and_then(|special_win| {
+                        let input = UserInput {
+                            hand_tiles,
+                            open_melds: self.open_melds.clone(),
+                            closed_kans: self.closed_kans.clone(),
+                            winning_tile,
+                            agari_type: self.agari_type,
+                            player_context: PlayerContext {
+                                jikaze: self.jikaze,
+                                is_oya: self.jikaze == Kaze::Ton,
+                                is_riichi: self.is_riichi,
+                                is_daburu_riichi: self.is_daburu_riichi,
+                                is_open_riichi: false,
+                                is_ippatsu: self.is_ippatsu,
+                                is_menzen: self.open_melds.is_empty(),
+                            },
+                            game_context: GameContext {
+                                bakaze: self.bakaze,
+                                kyoku: 1,
+                                honba: self.honba,
+                                riichi_bou: if self.is_riichi { 1 } else { 0 },
+                                dora_indicators: self.dora_indicators.clone(),
+                                uradora_indicators: self.uradora_indicators.clone(),
+                                num_akadora: 0,
+                                special_win,
+                            },
+                        };
+                        // Calculate score (all four round winds are selectable,
+                        // and the dora indicators may be left empty)
+                        let rules = Ruleset {
+                            max_bakaze: Kaze::Pei,
+                            require_dora_indicator: false,
+                            ..Ruleset::default()
+                        };
+                        calculate_agari_with_rules(&input, &rules)
+                    });
+                    self.score_result = Some(result.map_err(|e| format!("Error: {}", e)));
                     self.phase = Phase::Result;
                 }
             }
@@ -759,7 +768,7 @@ impl RiichiGui {
                     .filter(|y| !matches!(y, Yaku::Dora | Yaku::UraDora | Yaku::AkaDora))
                     .count();
 
-                if valid_yaku_count == 0 && limit_name.is_none() {
+                if valid_yaku_count == 0 && !result.is_limit() {
                     column![
                         text("No Yaku Found")
                             .size(30)
@@ -790,6 +799,9 @@ impl RiichiGui {
                             HandLimit::Baiman => "Baiman",
                             HandLimit::Sanbaiman => "Sanbaiman",
                             HandLimit::Yakuman => "Yakuman",
+                            HandLimit::KazoeYakuman => "Kazoe Yakuman",
+                            HandLimit::DoubleYakuman => "Double Yakuman",
+                            HandLimit::TripleYakuman => "Triple Yakuman",
                         };
                         text(limit_str)
                             .size(24)
@@ -799,7 +811,7 @@ impl RiichiGui {
                     };
 
                     // Han / Fu
-                    let han_fu_text = if limit_name.as_ref() == Some(&HandLimit::Yakuman) {
+                    let han_fu_text = if result.is_yakuman() && !result.is_kazoe_yakuman() {
                         text(format!("{} Han", han)).size(20)
                     } else {
                         text(format!("{} Han / {} Fu", han, fu)).size(20)
@@ -882,7 +894,7 @@ impl RiichiGui {
                     .align_items(iced::Alignment::Center)
                 }
             }
-            Some(Err(_)) => column![
+            Some(Err(e)) if e.contains("No Yaku Found") => column![
                 text("No Yaku Found")
                     .size(30)
                     .style(Color::from_rgb(0.8, 0.0, 0.0)),
@@ -893,6 +905,14 @@ impl RiichiGui {
             ]
             .spacing(15)
             .align_items(iced::Alignment::Center),
+            Some(Err(e)) => column![
+                text("Invalid Hand")
+                    .size(30)
+                    .style(Color::from_rgb(0.8, 0.0, 0.0)),
+                text(e).size(20)
+            ]
+            .spacing(15)
+            .align_items(iced::Alignment::Center),
             None => column![text("No result available.")],
         };
 
@@ -1165,35 +1185,7 @@ impl RiichiGui {
     }
 
     fn get_meld_tiles(&self, meld: &OpenMeldInput) -> Vec<Hai> {
-        let mut tiles = Vec::new();
-        match meld.mentsu_type {
-            MentsuType::Shuntsu => {
-                let start_idx = crate::implements::tiles::tile_to_index(&meld.representative_tile);
-                if start_idx < 27 {
-                    let suit_base = (start_idx / 9) * 9;
-
-                    for i in 0..3 {
-                        let idx = start_idx + i;
-                        if idx < suit_base + 9 {
-                            tiles.push(crate::implements::tiles::index_to_tile(idx));
-                        }
-                    }
-                } else {
-                    tiles.push(meld.representative_tile);
-                }
-            }
-            MentsuType::Koutsu => {
-                for _ in 0..3 {
-                    tiles.push(meld.representative_tile);
-                }
-            }
-            MentsuType::Kantsu => {
-                for _ in 0..4 {
-                    tiles.push(meld.representative_tile);
-                }
-            }
-        }
-        tiles
+        meld.tiles()
     }
 }
 
@@ -0,0 +1,190 @@
+// explainer.rs: Human-readable derivation of a score
+
+use super::notation::tile_notation;
+use super::score_calculator::basic_points_raw;
+use super::types::{
+    game::AgariType,
+    hand::Machi,
+    input::UserInput,
+    scoring::{AgariResult, Analysis},
+    tiles::Kaze,
+    yaku::Yaku,
+};
+use alloc::{format, string::String, vec::Vec};
+
+/// e.g. "Closed hand, ron on 8p (ryanmen). Yaku: Riichi (1), Pinfu (1), Tanyao (1), Dora 2.
+/// Total 5 han 30 fu → Mangan, 8000."
+pub fn explain(result: &AgariResult, analysis: &Analysis) -> String {
+    let hand = if analysis.is_menzen {
+        "Closed hand"
+    } else {
+        "Open hand"
+    };
+    let agari = match analysis.agari_type {
+        AgariType::Tsumo => "tsumo",
+        AgariType::Ron => "ron",
+    };
+    let mut text = format!(
+        "{}, {} on {} ({}).",
+        hand,
+        agari,
+        tile_notation(&analysis.winning_tile),
+        machi_name(analysis.machi)
+    );
+
+    // Yaku (dora grouped into one entry each)
+    let mut entries: Vec<String> = Vec::new();
+    let mut dora = [0u8; 3];
+    for (yaku, han) in &analysis.yaku_han {
+        match yaku {
+            Yaku::Dora => dora[0] += 1,
+            Yaku::UraDora => dora[1] += 1,
+            Yaku::AkaDora => dora[2] += 1,
+            _ if *han >= 13 => entries.push(match han / 13 {
+                1 => format!("{} (yakuman)", yaku),
+                n => format!("{} ({}x yakuman)", yaku, n),
+            }),
+            _ => entries.push(format!("{} ({})", yaku, han)),
+        }
+    }
+    for (name, count) in ["Dora", "UraDora", "AkaDora"].iter().zip(dora) {
+        if count > 0 {
+            entries.push(format!("{} {}", name, count));
+        }
+    }
+    text += &format!(" Yaku: {}.", entries.join(", "));
+
+    // Fu sources (only when no limit replaces the han/fu table)
+    if !result.is_limit() && !analysis.fu_sources.is_empty() {
+        let sources: Vec<String> = analysis
+            .fu_sources
+            .iter()
+            .map(|(name, fu)| format!("{} {}", name, fu))
+            .collect();
+        let raw: u32 = analysis.fu_sources.iter().map(|(_, fu)| *fu as u32).sum();
+        if raw == result.fu as u32 {
+            text += &format!(" Fu: {} = {}.", sources.join(", "), raw);
+        } else {
+            text += &format!(
+                " Fu: {} = {}, rounded up to {}.",
+                sources.join(", "),
+                raw,
+                result.fu
+            );
+        }
+    }
+
+    // Total (kazoe yakuman is reached by han, so it is totalled like the lower limits)
+    if result.is_yakuman() && !result.is_kazoe_yakuman() {
+        match result.han / 13 {
+            1 => text += &format!(" Total Yakuman → {}.", result.total_payment),
+            n => text += &format!(" Total Yakuman x{} → {}.", n, result.total_payment),
+        }
+    } else {
+        text += &format!(" Total {} han {} fu → ", result.han, result.fu);
+        if let Some(limit) = &result.limit_name {
+            // below 5 han the limit came from fu, so show what it replaced
+            if result.han < 5 {
+                let raw = basic_points_raw(result.han, result.fu);
+                text += &format!("{} (from {} basic points), ", limit, raw);
+            } else {
+                text += &format!("{}, ", limit);
+            }
+        }
+        text += &format!("{}.", result.total_payment);
+    }
+
+    text
+}
+
+/// One line describing the win, without scoring it, e.g. "South seat, East 1, Ron on 8p, closed."
+pub fn summary_line(input: &UserInput) -> String {
+    let player = &input.player_context;
+    let game = &input.game_context;
+    let agari = match input.agari_type {
+        AgariType::Tsumo => "Tsumo",
+        AgariType::Ron => "Ron",
+    };
+    let hand = if player.is_menzen { "closed" } else { "open" };
+    format!(
+        "{} seat, {} {}, {} on {}, {}.",
+        kaze_name(player.jikaze),
+        kaze_name(game.bakaze),
+        game.kyoku,
+        agari,
+        tile_notation(&input.winning_tile),
+        hand
+    )
+}
+
+fn kaze_name(kaze: Kaze) -> &'static str {
+    match kaze {
+        Kaze::Ton => "East",
+        Kaze::Nan => "South",
+        Kaze::Shaa => "West",
+        Kaze::Pei => "North",
+    }
+}
+
+fn machi_name(machi: Machi) -> &'static str {
+    match machi {
+        Machi::Ryanmen => "ryanmen",
+        Machi::Tanki => "tanki",
+        Machi::Penchan => "penchan",
+        Machi::Kanchan => "kanchan",
+        Machi::Shanpon => "shanpon",
+        Machi::KokushiIchimen => "single wait",
+        Machi::KokushiJusanmen => "13-sided wait",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::calculate_agari_with_analysis;
+    use crate::implements::rules::Ruleset;
+    use crate::implements::test_hands::{hand, tiles};
+
+    #[test]
+    fn explain_snapshot_of_the_example_hand() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.player_context.is_riichi = true;
+        input.game_context.dora_indicators = tiles("3s");
+        let (result, analysis) =
+            calculate_agari_with_analysis(&input, &Ruleset::default()).unwrap();
+        assert_eq!(
+            explain(&result, &analysis),
+            "Closed hand, ron on 8p (ryanmen). Yaku: Riichi (1), Pinfu (1), Tanyao (1), Dora 2. \
+         Total 5 han 30 fu → Mangan, 8000."
+        );
+    }
+
+    #[test]
+    fn explain_lists_fu_sources_below_the_limits() {
+        let input = hand("234567m345678p44s", "8p", false);
+        let (result, analysis) =
+            calculate_agari_with_analysis(&input, &Ruleset::default()).unwrap();
+        assert_eq!(
+            explain(&result, &analysis),
+            "Closed hand, ron on 8p (ryanmen). Yaku: Pinfu (1), Tanyao (1). \
+         Fu: Base 20, Menzen Ron 10 = 30. Total 2 han 30 fu → 2000."
+        );
+    }
+
+    #[test]
+    fn summary_line_of_the_example_input() {
+        let input = hand("234567m345678p44s", "8p", false);
+        assert_eq!(
+            summary_line(&input),
+            "South seat, East 1, Ron on 8p, closed."
+        );
+
+        let mut dealer = hand("234567m345678p44s", "8p", true);
+        dealer.player_context.jikaze = Kaze::Ton;
+        dealer.game_context.kyoku = 3;
+        assert_eq!(
+            summary_line(&dealer),
+            "East seat, East 3, Tsumo on 8p, closed."
+        );
+    }
+}
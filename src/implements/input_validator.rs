@@ -0,0 +1,620 @@
+// input_validator.rs: Rejects impossible inputs before the hand is organized
+
+use super::shanten_calculator::waits;
+use super::types::{
+    game::{AgariType, SpecialWin},
+    hand::MentsuType,
+    input::UserInput,
+    rules::Ruleset,
+    tiles::{Hai, Kaze, tile_to_index, try_tile_to_index},
+};
+use alloc::vec::Vec;
+
+/// Every check calculate_agari runs before scoring, without the scoring.
+/// Useful on its own, e.g. for feedback while a hand is being entered.
+pub fn validate_input(input: &UserInput) -> Result<(), &'static str> {
+    validate_input_with_rules(input, &Ruleset::default())
+}
+
+/// validate_input under a custom ruleset
+pub fn validate_input_with_rules(input: &UserInput, rules: &Ruleset) -> Result<(), &'static str> {
+    validate_tiles(input)?;
+    validate_hand_composition(input, rules)?;
+    validate_winning_tile(input)?;
+    validate_game_state(input, rules)?;
+    Ok(())
+}
+
+/// Player and round context that can't happen in a real game
+pub fn validate_game_state(input: &UserInput, rules: &Ruleset) -> Result<(), &'static str> {
+    let player = &input.player_context;
+    let game = &input.game_context;
+
+    // The dealer always sits East
+    if rules.check_dealer_seat && player.is_oya != (player.jikaze == Kaze::Ton) {
+        return Err("Invalid game state: the dealer (oya) must have East as seat wind.");
+    }
+
+    // Concealed means no open melds (closed kans keep the hand concealed).
+    // MenzenTsumo and the other closed-only yaku trust this flag.
+    if player.is_menzen != input.open_melds.is_empty() {
+        return Err(
+            "Invalid game state: is_menzen must be true exactly when there are no open melds.",
+        );
+    }
+
+    // Open Riichi is a way of declaring Riichi, not a separate declaration
+    if player.is_open_riichi && !(player.is_riichi || player.is_daburu_riichi) {
+        return Err("Invalid game state: Open Riichi requires a Riichi declaration.");
+    }
+
+    // Any call breaks ippatsu, including the kan before a rinshan draw
+    if player.is_ippatsu && game.special_win == SpecialWin::Rinshan {
+        return Err("Invalid game state: Ippatsu is broken by the kan before Rinshan Kaihou.");
+    }
+    if player.is_ippatsu && !input.open_melds.is_empty() {
+        return Err("Invalid game state: Ippatsu is impossible with open melds.");
+    }
+
+    // Each special win needs the matching kind of win
+    let is_tsumo = input.agari_type == AgariType::Tsumo;
+    let no_calls = input.open_melds.is_empty() && input.closed_kans.is_empty();
+    match game.special_win {
+        // Rinshan Kaihou is a Tsumo on the replacement tile drawn after a kan
+        SpecialWin::Rinshan => {
+            let has_kan = !input.closed_kans.is_empty()
+                || input
+                    .open_melds
+                    .iter()
+                    .any(|m| m.mentsu_type == MentsuType::Kantsu);
+            if !has_kan || !is_tsumo {
+                return Err("Invalid game state: Rinshan Kaihou needs a kan and a Tsumo win.");
+            }
+        }
+        // Blessings of heaven/earth/man: a first-turn win with no calls at all
+        SpecialWin::Tenhou if !(player.is_oya && is_tsumo && no_calls) => {
+            return Err(
+                "Invalid game state: Tenhou is the dealer's Tsumo on the first draw, with no calls.",
+            );
+        }
+        SpecialWin::Chiihou if !(!player.is_oya && is_tsumo && no_calls) => {
+            return Err(
+                "Invalid game state: Chiihou is a non-dealer's Tsumo on the first draw, with no calls.",
+            );
+        }
+        SpecialWin::Renhou if !(!player.is_oya && !is_tsumo && no_calls) => {
+            return Err(
+                "Invalid game state: Renhou is a non-dealer's Ron before the first draw, with no calls.",
+            );
+        }
+        // Chankan robs the tile being added to another player's pon (shouminkan):
+        // it is a Ron, and the other three copies sit in that player's meld
+        SpecialWin::Chankan => {
+            if is_tsumo {
+                return Err("Invalid game state: Chankan is a Ron on the tile added to a kan.");
+            }
+            let copies = all_tiles(input)
+                .iter()
+                .filter(|t| **t == input.winning_tile)
+                .count();
+            if copies > 1 {
+                return Err(
+                    "Invalid game state: Chankan needs the other 3 copies of the winning tile in the robbed kan.",
+                );
+            }
+        }
+        _ => {}
+    }
+
+    // Table counters (honba and riichi sticks only grow this large in theory)
+    if game.honba > rules.max_counter {
+        return Err("Invalid game state: honba count is above this ruleset's cap.");
+    }
+    if game.riichi_bou > rules.max_counter {
+        return Err("Invalid game state: riichi stick count is above this ruleset's cap.");
+    }
+
+    // Round wind
+    if game.bakaze > rules.max_bakaze {
+        return Err("Invalid game state: round wind is beyond the last round of this ruleset.");
+    }
+
+    Ok(())
+}
+
+// Every tile must be one of the 34 (a numbered tile is 1-9); the later checks index by tile
+fn validate_tiles(input: &UserInput) -> Result<(), &'static str> {
+    let game = &input.game_context;
+    let mut indicators = game.dora_indicators.iter().chain(&game.uradora_indicators);
+    if indicators.any(|t| try_tile_to_index(t).is_none()) {
+        return Err("Invalid dora indicators: a numbered tile must be 1-9.");
+    }
+
+    let meld_tiles = input.open_melds.iter().map(|m| &m.representative_tile);
+    let hand = input
+        .hand_tiles
+        .iter()
+        .chain([&input.winning_tile])
+        .chain(meld_tiles)
+        .chain(&input.closed_kans);
+    for tile in hand {
+        if try_tile_to_index(tile).is_none() {
+            return Err("Invalid tiles: a numbered tile must be 1-9.");
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_hand_composition(input: &UserInput, rules: &Ruleset) -> Result<(), &'static str> {
+    // Meld Count (each declared meld stands in for 3 of the 14 tiles)
+    let num_open = input.open_melds.len();
+    let num_kans = input.closed_kans.len();
+    if num_open > 4 {
+        return Err("Invalid melds: more than 4 open melds declared.");
+    }
+    if num_kans > 4 {
+        return Err("Invalid melds: more than 4 closed kans declared.");
+    }
+    if num_open + num_kans > 4 {
+        return Err("Invalid melds: open melds and closed kans add up to more than 4.");
+    }
+    // hand_tiles holds the concealed tiles only; a common mistake is to list meld tiles there too
+    let num_concealed = input.hand_tiles.len() + (input.agari_type == AgariType::Ron) as usize;
+    let num_meld_tiles: usize = input
+        .open_melds
+        .iter()
+        .map(|m| m.tiles().len())
+        .sum::<usize>()
+        + 4 * num_kans;
+    if num_meld_tiles > 0 && num_concealed == 14 - 3 * (num_open + num_kans) + num_meld_tiles {
+        return Err(
+            "Invalid hand: hand_tiles should not include the tiles of declared melds or kans.",
+        );
+    }
+    if num_concealed + 3 * (num_open + num_kans) > 14 {
+        return Err(
+            "Invalid hand: too many concealed tiles for the declared melds (more than 4 melds and a pair).",
+        );
+    }
+
+    // Akadora
+    let total_aka: u8 = rules.aka_per_suit.iter().sum();
+    if input.game_context.num_akadora > total_aka {
+        return Err("Invalid akadora count: more red fives than this ruleset has.");
+    }
+    // Each red five is a five of a suit with a red copy: e.g. under one red per suit,
+    // two 5p and no other fives allow only one
+    let tiles = all_tiles(input);
+    let mut fives_per_suit = [0u8; 3];
+    for tile in &tiles {
+        if let Hai::Suhai(5, suit) = tile {
+            fives_per_suit[*suit as usize] += 1;
+        }
+    }
+    let max_aka: u8 = fives_per_suit
+        .iter()
+        .zip(rules.aka_per_suit)
+        .map(|(&fives, aka)| fives.min(aka))
+        .sum();
+    if input.game_context.num_akadora > max_aka {
+        return Err(
+            "Invalid akadora count: more red fives than this ruleset allows for the fives in the hand.",
+        );
+    }
+
+    // Tile Availability: one multiset of hand, melds, kans and revealed indicators,
+    // so e.g. four in hand plus one in a pon is caught (they share 4 copies of each tile)
+    let mut board_counts = [0u8; 34];
+    let indicators = input
+        .game_context
+        .dora_indicators
+        .iter()
+        .chain(&input.game_context.uradora_indicators);
+    for tile in tiles.iter().chain(indicators) {
+        let count = &mut board_counts[tile_to_index(tile)];
+        *count = count.saturating_add(1);
+        if *count > 4 {
+            return Err(
+                "Invalid tiles: more than 4 copies of a tile across hand, melds and dora indicators.",
+            );
+        }
+    }
+
+    // Dora Indicators (1 + 1 per kan)
+    let open_kans = input
+        .open_melds
+        .iter()
+        .filter(|m| m.mentsu_type == MentsuType::Kantsu)
+        .count();
+    let total_kans = input.closed_kans.len() + open_kans;
+    let num_indicators = input.game_context.dora_indicators.len();
+
+    if num_indicators == 0 && rules.require_dora_indicator {
+        return Err("Invalid dora indicators: at least one indicator is always revealed.");
+    }
+    if num_indicators > 1 + total_kans {
+        return Err("Invalid dora indicators: only one extra indicator is revealed per kan.");
+    }
+
+    Ok(())
+}
+
+fn validate_winning_tile(input: &UserInput) -> Result<(), &'static str> {
+    let mut counts = [0u8; 34];
+    for tile in &input.hand_tiles {
+        counts[tile_to_index(tile)] = counts[tile_to_index(tile)].saturating_add(1);
+    }
+
+    // If Tsumo, the winning tile is in hand_tiles; take it out to get the pre-win hand
+    let win_index = tile_to_index(&input.winning_tile);
+    if input.agari_type == AgariType::Tsumo {
+        if counts[win_index] == 0 {
+            return Err("Invalid winning tile: a Tsumo winning tile must be in the hand.");
+        }
+        counts[win_index] -= 1;
+    }
+
+    // Bad tile counts are reported by organize_hand
+    let total: usize = counts.iter().map(|&c| c as usize).sum();
+    if counts.iter().any(|&c| c > 4) || total % 3 != 1 {
+        return Ok(());
+    }
+
+    // Multi-sided waits accept several tiles; the winning tile only has to be one of them
+    if !waits(&counts).contains(&input.winning_tile) {
+        // Called melds were complete before the win, so they can't be what it completed
+        let in_open_meld = input
+            .open_melds
+            .iter()
+            .any(|m| m.tiles().contains(&input.winning_tile));
+        if in_open_meld {
+            return Err(
+                "Invalid winning tile: it only appears in an open meld; it must complete a concealed group or the pair.",
+            );
+        }
+        return Err("Invalid winning tile: it does not complete the hand.");
+    }
+
+    Ok(())
+}
+
+// ---Helper Functions---
+
+// every tile of the winning hand, including melds, kans and a Ron winning tile
+pub(crate) fn all_tiles(input: &UserInput) -> Vec<Hai> {
+    let mut tiles = input.hand_tiles.clone();
+    if input.agari_type == AgariType::Ron {
+        tiles.push(input.winning_tile);
+    }
+    for meld in &input.open_melds {
+        tiles.extend(meld.tiles());
+    }
+    for kan_tile in &input.closed_kans {
+        tiles.extend([*kan_tile; 4]);
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::calculate_agari;
+    use crate::implements::game::SpecialWin;
+    use crate::implements::input::OpenMeldInput;
+    use crate::implements::test_hands::{dealer_hand, hand, tile, tiles};
+    use crate::implements::tiles::{Hai, Suhai};
+    use alloc::vec;
+
+    #[test]
+    fn two_indicators_without_a_kan_are_rejected() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        input.game_context.dora_indicators = tiles("7z1m");
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid dora indicators: only one extra indicator is revealed per kan.")
+        );
+    }
+
+    #[test]
+    fn missing_indicator_depends_on_the_ruleset() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        input.game_context.dora_indicators.clear();
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid dora indicators: at least one indicator is always revealed.")
+        );
+
+        let lenient = Ruleset {
+            require_dora_indicator: false,
+            ..Ruleset::default()
+        };
+        assert_eq!(validate_input_with_rules(&input, &lenient), Ok(()));
+    }
+
+    #[test]
+    fn akadora_is_bounded_by_the_fives_per_suit() {
+        // one 5m and no other fives: at most one red five
+        let mut input = hand("234567m123789p44s", "4s", false);
+        input.game_context.num_akadora = 2;
+        assert_eq!(
+            validate_input(&input),
+            Err(
+                "Invalid akadora count: more red fives than this ruleset allows for the fives in the hand."
+            )
+        );
+        input.game_context.num_akadora = 1;
+        assert_eq!(validate_input(&input), Ok(()));
+    }
+
+    #[test]
+    fn winning_tile_must_be_one_of_the_waits() {
+        // 34567p waits on 2p, 5p and 8p
+        let mut input = hand("345678m234567p44s", "2p", false);
+        for win in ["2p", "5p", "8p"] {
+            input.winning_tile = tile(win);
+            assert_eq!(validate_input(&input), Ok(()), "{win}");
+        }
+
+        input.winning_tile = tile("9p");
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid winning tile: it does not complete the hand.")
+        );
+    }
+
+    #[test]
+    fn dealer_must_sit_east_unless_the_check_is_off() {
+        let mut input = dealer_hand("234567m345678p44s", "8p", true);
+        input.player_context.jikaze = Kaze::Nan;
+        let err = Err("Invalid game state: the dealer (oya) must have East as seat wind.");
+        assert_eq!(validate_input(&input), err);
+
+        // A non-dealer in the East seat is just as inconsistent
+        let mut east = hand("234567m345678p44s", "8p", true);
+        east.player_context.jikaze = Kaze::Ton;
+        assert_eq!(validate_input(&east), err);
+
+        let lenient = Ruleset {
+            check_dealer_seat: false,
+            ..Ruleset::default()
+        };
+        assert_eq!(validate_input_with_rules(&input, &lenient), Ok(()));
+    }
+
+    #[test]
+    fn round_wind_is_bounded_by_the_ruleset() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        input.game_context.bakaze = Kaze::Shaa;
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid game state: round wind is beyond the last round of this ruleset.")
+        );
+
+        let long_game = Ruleset {
+            max_bakaze: Kaze::Pei,
+            ..Ruleset::default()
+        };
+        assert_eq!(validate_input_with_rules(&input, &long_game), Ok(()));
+    }
+
+    #[test]
+    fn an_indicator_cannot_be_a_fifth_copy() {
+        let mut input = hand("111123m456p789s55s", "5s", true);
+        assert_eq!(validate_input(&input), Ok(()));
+
+        input.game_context.dora_indicators = tiles("1m");
+        assert_eq!(
+            validate_input(&input),
+            Err(
+                "Invalid tiles: more than 4 copies of a tile across hand, melds and dora indicators."
+            )
+        );
+    }
+
+    #[test]
+    fn a_four_aka_ruleset_allows_a_fourth_red_five() {
+        // one 5m, three 5p, one 5s
+        let mut input = hand("345m34555p345678s", "8s", true);
+        input.game_context.num_akadora = 4;
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid akadora count: more red fives than this ruleset has.")
+        );
+
+        let four_aka = Ruleset {
+            aka_per_suit: [1, 2, 1],
+            ..Ruleset::default()
+        };
+        assert_eq!(validate_input_with_rules(&input, &four_aka), Ok(()));
+        input.game_context.num_akadora = 3;
+        assert_eq!(validate_input(&input), Ok(()));
+    }
+
+    #[test]
+    fn ippatsu_conflicts_with_rinshan_and_open_melds() {
+        let mut input = hand("234567p23488s", "4s", true);
+        input.closed_kans = vec![tile("2m")];
+        input.game_context.dora_indicators = tiles("7z1z");
+        input.game_context.special_win = SpecialWin::Rinshan;
+        input.player_context.is_riichi = true;
+        assert_eq!(validate_input(&input), Ok(()));
+
+        input.player_context.is_ippatsu = true;
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid game state: Ippatsu is broken by the kan before Rinshan Kaihou.")
+        );
+
+        let mut open = hand("567m345678p44s", "8p", true);
+        open.open_melds = vec![OpenMeldInput::chi(tile("2m"))];
+        open.player_context.is_menzen = false;
+        open.player_context.is_ippatsu = true;
+        assert_eq!(
+            validate_input(&open),
+            Err("Invalid game state: Ippatsu is impossible with open melds.")
+        );
+    }
+
+    #[test]
+    fn winning_tile_only_in_an_open_meld_is_rejected() {
+        // 4567s waits on 4s or 7s; 7p only appears in the pon
+        let mut input = hand("234567m4567s7s", "7s", false);
+        input.open_melds = vec![OpenMeldInput::pon(tile("7p"))];
+        input.player_context.is_menzen = false;
+        input.winning_tile = tile("7p");
+        assert_eq!(
+            validate_input(&input),
+            Err(
+                "Invalid winning tile: it only appears in an open meld; it must complete a concealed group or the pair."
+            )
+        );
+
+        input.winning_tile = tile("7s");
+        assert_eq!(validate_input(&input), Ok(()));
+    }
+
+    #[test]
+    fn honba_above_the_cap_is_rejected() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        input.game_context.honba = 20;
+        assert_eq!(validate_input(&input), Ok(()));
+
+        input.game_context.honba = 21;
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid game state: honba count is above this ruleset's cap.")
+        );
+
+        let generous = Ruleset {
+            max_counter: 200,
+            ..Ruleset::default()
+        };
+        input.game_context.honba = 200;
+        assert_eq!(validate_input_with_rules(&input, &generous), Ok(()));
+    }
+
+    #[test]
+    fn validate_input_checks_legality_without_scoring() {
+        let mut input = hand("567m345678p44s", "8p", false);
+        input.open_melds = vec![OpenMeldInput::chi(tile("2m"))];
+        assert_eq!(
+            validate_input(&input),
+            Err("Invalid game state: is_menzen must be true exactly when there are no open melds.")
+        );
+
+        // legal but yakuless: only scoring rejects it
+        let mut yakuless = hand("456p789p234s55s", "4s", false);
+        yakuless.open_melds = vec![OpenMeldInput::chi(tile("1m"))];
+        yakuless.player_context.is_menzen = false;
+        assert_eq!(validate_input(&yakuless), Ok(()));
+        assert!(calculate_agari(&yakuless).is_err());
+    }
+
+    #[test]
+    fn meld_overflow_names_its_cause() {
+        let mut declared = hand("11z", "1z", false);
+        declared.player_context.is_menzen = false;
+        declared.open_melds = ["2m", "5m", "8p", "3s", "6s"]
+            .map(|t| OpenMeldInput::pon(tile(t)))
+            .to_vec();
+        assert_eq!(
+            validate_input(&declared),
+            Err("Invalid melds: more than 4 open melds declared.")
+        );
+        declared.open_melds.truncate(3);
+        declared.closed_kans = vec![tile("9m"), tile("9p")];
+        assert_eq!(
+            validate_input(&declared),
+            Err("Invalid melds: open melds and closed kans add up to more than 4.")
+        );
+
+        // two pons leave room for 8 concealed tiles, not 11
+        let mut implied = hand("234567m345p44s", "4s", false);
+        implied.player_context.is_menzen = false;
+        implied.open_melds = vec![
+            OpenMeldInput::pon(tile("2z")),
+            OpenMeldInput::pon(tile("3z")),
+        ];
+        assert_eq!(
+            validate_input(&implied),
+            Err(
+                "Invalid hand: too many concealed tiles for the declared melds (more than 4 melds and a pair)."
+            )
+        );
+    }
+
+    #[test]
+    fn chankan_must_match_a_robbed_shouminkan() {
+        // 2p is only the winning tile: the other three can be in the robbed kan
+        let mut input = hand("345678m234567p44s", "2p", false);
+        input.game_context.special_win = SpecialWin::Chankan;
+        assert_eq!(validate_input(&input), Ok(()));
+
+        // a second 5p in hand leaves at most two copies for the kan
+        input.winning_tile = tile("5p");
+        assert_eq!(
+            validate_input(&input),
+            Err(
+                "Invalid game state: Chankan needs the other 3 copies of the winning tile in the robbed kan."
+            )
+        );
+
+        let mut tsumo = hand("345678m234567p44s", "2p", true);
+        tsumo.game_context.special_win = SpecialWin::Chankan;
+        assert_eq!(
+            validate_input(&tsumo),
+            Err("Invalid game state: Chankan is a Ron on the tile added to a kan.")
+        );
+    }
+
+    #[test]
+    fn four_in_hand_and_one_in_a_meld_is_a_fifth_copy() {
+        let mut input = hand("111123m456p99s", "9s", false);
+        input.open_melds = vec![OpenMeldInput::chi(tile("1m"))];
+        input.player_context.is_menzen = false;
+        assert_eq!(
+            validate_input(&input),
+            Err(
+                "Invalid tiles: more than 4 copies of a tile across hand, melds and dora indicators."
+            )
+        );
+
+        input.open_melds = vec![OpenMeldInput::chi(tile("2m"))];
+        assert_eq!(validate_input(&input), Ok(()));
+    }
+
+    #[test]
+    fn malformed_indicators_are_a_clear_error() {
+        let err = Err("Invalid dora indicators: a numbered tile must be 1-9.");
+
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.game_context.dora_indicators = vec![Hai::Suhai(0, Suhai::Manzu)];
+        assert_eq!(validate_input(&input), err);
+        assert_eq!(calculate_agari(&input).err(), err.err());
+
+        let mut ura = hand("234567m345678p44s", "8p", false);
+        ura.player_context.is_riichi = true;
+        ura.game_context.uradora_indicators = vec![Hai::Suhai(10, Suhai::Souzu)];
+        assert_eq!(validate_input(&ura), err);
+    }
+
+    #[test]
+    fn two_red_5p_need_a_ruleset_with_two() {
+        // the 55p pair is the only fives
+        let mut input = hand("234m123p55p678s789s", "8s", false);
+        input.game_context.num_akadora = 2;
+        assert_eq!(
+            validate_input(&input),
+            Err(
+                "Invalid akadora count: more red fives than this ruleset allows for the fives in the hand."
+            )
+        );
+
+        let two_red_5p = Ruleset {
+            aka_per_suit: [1, 2, 1],
+            ..Ruleset::default()
+        };
+        assert_eq!(validate_input_with_rules(&input, &two_red_5p), Ok(()));
+    }
+}
@@ -6,24 +6,494 @@ pub mod yaku_checker;
 pub use yaku_checker::*;
 pub mod score_calculator;
 pub use score_calculator::*;
+pub mod input_validator;
+pub use input_validator::*;
+pub mod shanten_calculator;
+pub use shanten_calculator::*;
+pub mod notation;
+pub use notation::*;
+pub mod explainer;
+pub use explainer::*;
+#[cfg(feature = "cache")]
+pub mod score_cache;
+#[cfg(feature = "test-util")]
+pub mod testgen;
+#[cfg(feature = "cache")]
+pub use score_cache::*;
+#[cfg(test)]
+pub(crate) mod test_hands;
 
+use crate::implements::game::{AgariType, GameContext, PlayerContext};
+use crate::implements::hand::HandOrganization;
 use crate::implements::input::UserInput;
-use crate::implements::scoring::AgariResult;
+use crate::implements::rules::Ruleset;
+use crate::implements::scoring::{AgariResult, Analysis, ScoreReport};
+use crate::implements::tiles::{Hai, tile_to_index};
+use crate::implements::yaku::Yaku;
+use alloc::{string::String, vec::Vec};
 
 pub fn calculate_agari(input: &UserInput) -> Result<AgariResult, &'static str> {
+    calculate_agari_with_rules(input, &Ruleset::default())
+}
+
+pub fn calculate_agari_with_rules(
+    input: &UserInput,
+    rules: &Ruleset,
+) -> Result<AgariResult, &'static str> {
+    calculate_agari_with_analysis(input, rules).map(|(result, _)| result)
+}
+
+/// Same as calculate_agari_with_rules, plus the Analysis used by explain
+pub fn calculate_agari_with_analysis(
+    input: &UserInput,
+    rules: &Ruleset,
+) -> Result<(AgariResult, Analysis), &'static str> {
     // Get input
     let player = &input.player_context;
     let game = &input.game_context;
     let agari_type = input.agari_type;
 
-    // Organize Hand
-    let organization = organize_hand(input)?;
+    // Validate Input
+    validate_input_with_rules(input, rules)?;
+
+    // Score every reading of the hand and keep the highest
+    let mut best: Option<(AgariResult, Analysis)> = None;
+    let mut first_error = None;
+
+    for organization in organize_candidates(input)? {
+        // Check Yaku
+        let yaku_result = match check_all_yaku(organization, player, game, agari_type, rules) {
+            Ok(yaku_result) => yaku_result,
+            Err(e) => {
+                first_error.get_or_insert(e);
+                continue;
+            }
+        };
+
+        // Calculate Final Score
+        let analysis = analyze_score(&yaku_result, player, game, agari_type, rules);
+        let score = calculate_score(yaku_result, player, game, agari_type, rules);
+
+        let is_better = best.as_ref().is_none_or(|(current, _)| {
+            (score.total_payment, score.han, score.fu)
+                > (current.total_payment, current.han, current.fu)
+        });
+        if is_better {
+            best = Some((score, analysis));
+        }
+    }
+
+    best.ok_or(first_error.unwrap_or("No Yaku Found"))
+}
+
+/// Score the hand and gather the result and its analysis into one ScoreReport
+pub fn full_report(input: &UserInput, rules: &Ruleset) -> Result<ScoreReport, &'static str> {
+    let (result, analysis) = calculate_agari_with_analysis(input, rules)?;
+    let dora_han: u8 = analysis
+        .yaku_han
+        .iter()
+        .filter(|(yaku, _)| matches!(yaku, Yaku::Dora | Yaku::UraDora | Yaku::AkaDora))
+        .map(|(_, han)| han)
+        .sum();
+
+    Ok(ScoreReport {
+        han: result.han,
+        yaku_han: result.han - dora_han,
+        dora_han,
+        fu: result.fu,
+        fu_sources: analysis.fu_sources,
+        limit_name: result.limit_name,
+        total_payment: result.total_payment,
+        oya_payment: result.oya_payment,
+        ko_payment: result.ko_payment,
+        honba_bonus: result.honba_bonus,
+        riichi_bou: result.riichi_bou,
+        agari_type: result.agari_type,
+        winning_tile: analysis.winning_tile,
+        machi: analysis.machi,
+        yaku: analysis.yaku_han,
+        is_menzen: result.is_menzen,
+    })
+}
+
+/// The same hand scored under each named ruleset, in order (e.g. kiriage on and off)
+pub fn score_multi(
+    input: &UserInput,
+    rulesets: &[(&str, Ruleset)],
+) -> Vec<(String, Result<AgariResult, &'static str>)> {
+    rulesets
+        .iter()
+        .map(|(name, rules)| {
+            (
+                String::from(*name),
+                calculate_agari_with_rules(input, rules),
+            )
+        })
+        .collect()
+}
+
+/// Han only (dora included, 13 per yakuman) of the highest-han reading, skipping fu and payments
+pub fn quick_han(input: &UserInput, rules: &Ruleset) -> Result<u8, &'static str> {
+    let player = &input.player_context;
+
+    validate_input_with_rules(input, rules)?;
+
+    // Highest han over every reading of the hand
+    let mut best = None;
+    let mut first_error = None;
+    for organization in organize_candidates(input)? {
+        match check_all_yaku(
+            organization,
+            player,
+            &input.game_context,
+            input.agari_type,
+            rules,
+        ) {
+            Ok(yaku_result) => {
+                let han = count_han(&yaku_result.yaku_list, player.is_menzen, rules);
+                best = best.max(Some(han));
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    best.ok_or(first_error.unwrap_or("No Yaku Found"))
+}
+
+/// Yaku of each winning tile of a closed 13-tile tenpai hand, as a Ron under
+/// rules (highest-han reading; empty if that tile gives no yaku)
+pub fn yaku_by_wait(
+    counts: &[u8; 34],
+    player: &PlayerContext,
+    game: &GameContext,
+    rules: &Ruleset,
+) -> Vec<(Hai, Vec<Yaku>)> {
+    let mut result = Vec::new();
+
+    for agari_hai in waits(counts) {
+        let mut complete = *counts;
+        complete[tile_to_index(&agari_hai)] += 1;
+
+        // Keep the reading with the most han
+        let mut best: Option<(u8, Vec<Yaku>)> = None;
+        for organization in count_candidates(&complete, agari_hai) {
+            if let Ok(yaku_result) =
+                check_all_yaku(organization, player, game, AgariType::Ron, rules)
+            {
+                let han = count_han(&yaku_result.yaku_list, player.is_menzen, rules);
+                if best.as_ref().is_none_or(|(best_han, _)| han > *best_han) {
+                    best = Some((han, yaku_result.yaku_list));
+                }
+            }
+        }
+        result.push((agari_hai, best.map(|(_, yaku)| yaku).unwrap_or_default()));
+    }
+    result
+}
+
+/// Average Ron payment of a closed 13-tile tenpai hand over its waits, each weighted
+/// by the copies left after visible (dora indicators, melds, discards). Waits with
+/// no yaku or no copies left can't be won on and are skipped; 0.0 if none remain.
+pub fn expected_value(
+    counts: &[u8; 34],
+    visible: &[Hai],
+    player: &PlayerContext,
+    game: &GameContext,
+    rules: &Ruleset,
+) -> f64 {
+    // for a tenpai hand, the tiles that lower the shanten are exactly the waits
+    if shanten(counts) != 0 {
+        return 0.0;
+    }
+    let mut weighted_points = 0.0;
+    let mut total_weight = 0.0;
+
+    for (agari_hai, remaining) in ukeire_with_visible(counts, visible) {
+        if remaining == 0 {
+            continue;
+        }
+        let mut complete = *counts;
+        complete[tile_to_index(&agari_hai)] += 1;
+
+        // Highest payment over every reading, as calculate_agari picks it
+        let best = count_candidates(&complete, agari_hai)
+            .into_iter()
+            .filter_map(|organization| {
+                check_all_yaku(organization, player, game, AgariType::Ron, rules).ok()
+            })
+            .map(|yaku_result| {
+                calculate_score(yaku_result, player, game, AgariType::Ron, rules).total_payment
+            })
+            .max();
+        if let Some(points) = best {
+            weighted_points += points as f64 * remaining as f64;
+            total_weight += remaining as f64;
+        }
+    }
+
+    if total_weight == 0.0 {
+        0.0
+    } else {
+        weighted_points / total_weight
+    }
+}
+
+// every reading of complete concealed counts: the standard ones, then the irregular one
+fn count_candidates(complete: &[u8; 34], agari_hai: Hai) -> Vec<HandOrganization> {
+    let mut candidates: Vec<HandOrganization> = organize_counts(complete, agari_hai)
+        .unwrap_or_default()
+        .into_iter()
+        .map(HandOrganization::YonmentsuIchiatama)
+        .collect();
+    candidates.push(HandOrganization::Irregular {
+        counts: *complete,
+        agari_hai,
+    });
+    candidates
+}
+
+/// Scores each hand on the rayon thread pool. calculate_agari is pure, so
+/// results match scoring the batch sequentially, in the same order.
+#[cfg(feature = "rayon")]
+pub fn score_batch_par(inputs: &[UserInput]) -> Vec<Result<AgariResult, &'static str>> {
+    use rayon::prelude::*;
+
+    inputs.par_iter().map(calculate_agari).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::hand::{HandStructure, Machi};
+    use crate::implements::test_hands::{counts, hand, tile, tiles};
+    use crate::implements::yaku::Yaku;
+    use alloc::vec;
+
+    #[test]
+    fn chiitoitsu_ron_golden_result() {
+        let input = hand("1155m2277p3399s11z", "1z", false);
+        let pair = |notation| (tile(notation), tile(notation));
+        let expected = AgariResult {
+            han: 2,
+            fu: 25,
+            yaku_list: vec![Yaku::Chiitoitsu],
+            num_akadora: 0,
+            limit_name: None,
+            base_points: 1600,
+            oya_payment: 0,
+            ko_payment: 0,
+            total_payment: 1600,
+            honba: 0,
+            honba_bonus: 0,
+            riichi_bou: 0,
+            agari_type: AgariType::Ron,
+            is_oya: false,
+            is_menzen: true,
+            structure: Some(HandStructure::Chiitoitsu {
+                pairs: [
+                    pair("1m"),
+                    pair("5m"),
+                    pair("2p"),
+                    pair("7p"),
+                    pair("3s"),
+                    pair("9s"),
+                    pair("1z"),
+                ],
+                agari_hai: tile("1z"),
+                machi: Machi::Tanki,
+            }),
+        };
+        assert_eq!(calculate_agari(&input), Ok(expected));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_batch_matches_sequential() {
+        use alloc::vec::Vec;
+
+        let mut inputs = Vec::new();
+        for _ in 0..16 {
+            inputs.push(hand("234567m345678p44s", "8p", true));
+            inputs.push(hand("1155m2277p3399s11z", "1z", false));
+            inputs.push(hand("119m19p19s1234567z", "1m", false));
+            // Not a winning hand
+            inputs.push(hand("1155m2277p3399s12z", "1z", false));
+        }
+
+        let sequential: Vec<_> = inputs.iter().map(calculate_agari).collect();
+        assert_eq!(score_batch_par(&inputs), sequential);
+    }
+
+    #[test]
+    fn quick_han_matches_the_full_score() {
+        let mut riichi_dora = hand("234567m345678p44s", "8p", false);
+        riichi_dora.player_context.is_riichi = true;
+        riichi_dora.game_context.dora_indicators = tiles("3s");
+        let hands = [
+            riichi_dora,
+            hand("1155m2277p3399s11z", "1z", false),
+            hand("119m19p19s1234567z", "9m", false),
+            hand("223344m223344p55s", "5s", true),
+            hand("11122233344455m", "5m", false),
+        ];
+
+        let rules = Ruleset::default();
+        for input in &hands {
+            let han = calculate_agari(input).map(|result| result.han);
+            assert_eq!(quick_han(input, &rules), han);
+        }
+        assert_eq!(quick_han(&hands[2], &rules), Ok(13));
+    }
+
+    #[test]
+    fn suuankou_tanki_reading_beats_the_sequence_reading() {
+        // 5m completes 55m (four concealed triplets) or 345m (22m pair, 234m 345m 345m)
+        let result = calculate_agari(&hand("22233344455m777p", "5m", false)).unwrap();
+        assert_eq!(result.yaku_list, vec![Yaku::SuuankouTanki]);
+        assert!(result.is_yakuman());
+        assert_eq!(result.total_payment, 64000);
+    }
+
+    #[test]
+    fn riichi_with_an_ankan_stays_concealed() {
+        let mut input = hand("234567p23488s", "4s", false);
+        input.closed_kans = vec![tile("2m")];
+        input.player_context.is_riichi = true;
+        input.game_context.dora_indicators = tiles("7z1z");
+        let result = calculate_agari(&input).unwrap();
+
+        assert_eq!(result.yaku_list, vec![Yaku::Riichi, Yaku::Tanyao]);
+        // 20 base + 10 closed ron + 16 concealed simple kan = 46, rounded up
+        assert_eq!((result.han, result.fu), (2, 50));
+        assert_eq!(result.total_payment, 3200);
+    }
+
+    #[test]
+    fn yaku_by_wait_differs_per_winning_tile() {
+        // 23456m waits on 1m, 4m and 7m; only 1m breaks Tanyao
+        let input = hand("234567m345678p44s", "8p", false);
+        let by_wait = yaku_by_wait(
+            &counts("23456m345678p44s"),
+            &input.player_context,
+            &input.game_context,
+            &Ruleset::default(),
+        );
+
+        let pinfu_tanyao = vec![Yaku::Pinfu, Yaku::Tanyao];
+        assert_eq!(
+            by_wait,
+            vec![
+                (tile("1m"), vec![Yaku::Pinfu]),
+                (tile("4m"), pinfu_tanyao.clone()),
+                (tile("7m"), pinfu_tanyao),
+            ]
+        );
+
+        // the caller's rules apply: open riichi only scores when the ruleset has it
+        let mut player = input.player_context;
+        player.is_riichi = true;
+        player.is_open_riichi = true;
+        let open_riichi = Ruleset {
+            open_riichi: true,
+            ..Ruleset::default()
+        };
+        let with_rule = |rules: &Ruleset| {
+            yaku_by_wait(
+                &counts("23456m345678p44s"),
+                &player,
+                &input.game_context,
+                rules,
+            )
+        };
+        assert!(
+            with_rule(&open_riichi)
+                .iter()
+                .all(|(_, yaku)| yaku.contains(&Yaku::OpenRiichi))
+        );
+        assert!(
+            !with_rule(&Ruleset::default())
+                .iter()
+                .any(|(_, yaku)| yaku.contains(&Yaku::OpenRiichi))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn example_report_serializes() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.player_context.is_riichi = true;
+        input.game_context.dora_indicators = tiles("3s");
+        let report = full_report(&input, &Ruleset::default()).unwrap();
+
+        let json = serde_json::to_value(&report).unwrap();
+        let expected = serde_json::json!({
+            "han": 5,
+            "yaku_han": 3,
+            "dora_han": 2,
+            "fu": 30,
+            "fu_sources": [["Base", 20], ["Menzen Ron", 10]],
+            "limit_name": "Mangan",
+            "total_payment": 8000,
+            "oya_payment": 0,
+            "ko_payment": 0,
+            "honba_bonus": 0,
+            "riichi_bou": 0,
+            "agari_type": "Ron",
+            "winning_tile": { "Suhai": [8, "Pinzu"] },
+            "machi": "Ryanmen",
+            "yaku": [["Riichi", 1], ["Pinfu", 1], ["Tanyao", 1], ["Dora", 1], ["Dora", 1]],
+            "is_menzen": true,
+        });
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn score_multi_compares_kiriage() {
+        // riichi ippatsu pinfu tanyao: 4 han 30 fu ron
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.player_context.is_riichi = true;
+        input.player_context.is_ippatsu = true;
+        let kiriage = Ruleset {
+            kiriage_mangan: true,
+            ..Ruleset::default()
+        };
 
-    // Check Yaku
-    let yaku_result = check_all_yaku(organization, player, game, agari_type)?;
+        let scored = score_multi(
+            &input,
+            &[("standard", Ruleset::default()), ("kiriage", kiriage)],
+        );
+        assert_eq!(scored.len(), 2);
+        let (name, standard) = &scored[0];
+        assert_eq!(name, "standard");
+        let standard = standard.as_ref().unwrap();
+        assert_eq!((standard.han, standard.fu), (4, 30));
+        assert_eq!(standard.total_payment, 7700);
+        let (name, kiriage) = &scored[1];
+        assert_eq!(name, "kiriage");
+        assert_eq!(kiriage.as_ref().unwrap().total_payment, 8000);
+    }
 
-    // Calculate Final Score
-    let final_score = calculate_score(yaku_result, player, game, agari_type);
+    #[test]
+    fn expected_value_weights_waits_by_copies_left() {
+        // 23m waits on 1m (pinfu, 1000) and 4m (pinfu tanyao, 2000)
+        let hand_counts = counts("23m567m456p678p88s");
+        let input = hand("23m567m456p678p88s4m", "4m", false);
+        let ev = |visible: &[Hai]| {
+            expected_value(
+                &hand_counts,
+                visible,
+                &input.player_context,
+                &input.game_context,
+                &Ruleset::default(),
+            )
+        };
 
-    Ok(final_score)
+        assert_eq!(ev(&[]), 1500.0);
+        // two 4m seen: (4 * 1000 + 2 * 2000) / 6
+        assert!((ev(&tiles("44m")) - 8000.0 / 6.0).abs() < 1e-9);
+        // every 1m seen: only 4m is left
+        assert_eq!(ev(&tiles("1111m")), 2000.0);
+    }
 }
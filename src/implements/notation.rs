@@ -0,0 +1,221 @@
+// notation.rs: Parses compact tile notation, e.g. "123m456p789s11z"
+
+use super::input_validator::all_tiles;
+use super::types::{
+    hand::MentsuType,
+    input::UserInput,
+    tiles::{Hai, Suhai, index_to_tile, tile_to_index},
+};
+use alloc::{format, string::String, vec::Vec};
+
+/// Digits followed by a suit letter: m/p/s = 1-9, z = 1-7 (East, South, West, North, Haku, Hatsu, Chun)
+pub fn parse_tiles(notation: &str) -> Result<Vec<Hai>, &'static str> {
+    let mut tiles = Vec::new();
+    let mut numbers: Vec<u8> = Vec::new();
+
+    for c in notation.chars().filter(|c| !c.is_whitespace()) {
+        if let Some(n) = c.to_digit(10) {
+            numbers.push(n as u8);
+            continue;
+        }
+
+        if numbers.is_empty() {
+            return Err("Invalid notation: suit letter without numbers.");
+        }
+        for &n in &numbers {
+            let tile = match c {
+                'm' | 'p' | 's' if !(1..=9).contains(&n) => {
+                    return Err("Invalid notation: numbered tiles must be 1-9.");
+                }
+                'm' => Hai::Suhai(n, Suhai::Manzu),
+                'p' => Hai::Suhai(n, Suhai::Pinzu),
+                's' => Hai::Suhai(n, Suhai::Souzu),
+                'z' if !(1..=7).contains(&n) => {
+                    return Err("Invalid notation: honor tiles must be 1-7z.");
+                }
+                'z' => index_to_tile(26 + n as usize),
+                _ => return Err("Invalid notation: unknown suit letter (use m, p, s or z)."),
+            };
+            tiles.push(tile);
+        }
+        numbers.clear();
+    }
+
+    if !numbers.is_empty() {
+        return Err("Invalid notation: numbers without a suit letter.");
+    }
+    Ok(tiles)
+}
+
+/// Exactly one tile, e.g. "8p"
+pub fn parse_tile(notation: &str) -> Result<Hai, &'static str> {
+    match parse_tiles(notation)?.as_slice() {
+        [tile] => Ok(*tile),
+        _ => Err("Invalid notation: expected exactly one tile."),
+    }
+}
+
+/// Tile code of Tenhou's JSON logs (tenhou.net/6): man 11-19, pin 21-29, sou 31-39,
+/// honors 41-47 (East, South, West, North, Haku, Hatsu, Chun) and red fives 51/52/53
+pub fn tenhou_code(tile: &Hai, is_red: bool) -> u8 {
+    let index = tile_to_index(tile);
+    match tile {
+        Hai::Suhai(5, suit) if is_red => 51 + *suit as u8,
+        _ => ((index / 9 + 1) * 10 + index % 9 + 1) as u8,
+    }
+}
+
+/// The tile (and whether it is a red five) for a tenhou_code, None for an unused code
+pub fn from_tenhou_code(code: u8) -> Option<(Hai, bool)> {
+    let (suit, number) = (code / 10, code % 10);
+    match (suit, number) {
+        (1..=3, 1..=9) | (4, 1..=7) => Some((
+            index_to_tile((suit as usize - 1) * 9 + number as usize - 1),
+            false,
+        )),
+        (5, 1..=3) => Some((index_to_tile((number as usize - 1) * 9 + 4), true)),
+        _ => None,
+    }
+}
+
+// red fives per suit (man, pin, sou) for num_akadora, handed out one suit at a
+// time to suits that have a five (usual set: one per suit)
+fn red_fives(tiles: &[Hai], num_akadora: u8) -> [u8; 3] {
+    let mut fives = [0u8; 3];
+    for tile in tiles {
+        if let Hai::Suhai(5, suit) = tile {
+            fives[*suit as usize] += 1;
+        }
+    }
+
+    let mut red = [0u8; 3];
+    let mut remaining = num_akadora;
+    while remaining > 0 {
+        let mut marked = false;
+        for suit in 0..3 {
+            if remaining > 0 && red[suit] < fives[suit] {
+                red[suit] += 1;
+                remaining -= 1;
+                marked = true;
+            }
+        }
+        if !marked {
+            break;
+        }
+    }
+    red
+}
+
+/// Query string of Tenhou's hand viewer (https://tenhou.net/2/?q=...) for the
+/// 14-tile hand, melds included, with num_akadora fives written as red (0),
+/// e.g. "q=234067m345678p44s". Each kan is written as 3 tiles, since the viewer
+/// only reads 14. The viewer takes no dora indicators; use tenhou_code for those.
+pub fn to_tenhou_query(input: &UserInput) -> String {
+    // all_tiles includes the winning tile for both Ron and Tsumo, and 4 tiles per kan
+    let mut tiles = all_tiles(input);
+    let open_kans = input
+        .open_melds
+        .iter()
+        .filter(|meld| meld.mentsu_type == MentsuType::Kantsu)
+        .map(|meld| meld.representative_tile);
+    for kan_tile in input.closed_kans.iter().copied().chain(open_kans) {
+        if let Some(pos) = tiles.iter().position(|t| *t == kan_tile) {
+            tiles.remove(pos);
+        }
+    }
+    tiles.sort_unstable_by_key(tile_to_index);
+    let mut red = red_fives(&tiles, input.game_context.num_akadora);
+
+    let mut query = String::from("q=");
+    for (suit, letter) in ['m', 'p', 's', 'z'].into_iter().enumerate() {
+        let mut digits = String::new();
+        for tile in tiles.iter().filter(|t| tile_to_index(t) / 9 == suit) {
+            let number = tile_to_index(tile) % 9 + 1;
+            if number == 5 && suit < 3 && red[suit] > 0 {
+                red[suit] -= 1;
+                digits.push('0');
+            } else {
+                digits.push_str(&format!("{}", number));
+            }
+        }
+        if !digits.is_empty() {
+            query.push_str(&digits);
+            query.push(letter);
+        }
+    }
+    query
+}
+
+/// A single tile in notation, e.g. "8p" or "1z"
+pub fn tile_notation(tile: &Hai) -> String {
+    let index = tile_to_index(tile);
+    let suit = ['m', 'p', 's', 'z'][index / 9];
+    format!("{}{}", index % 9 + 1, suit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::input::OpenMeldInput;
+    use crate::implements::test_hands::{hand, tile};
+    use alloc::vec;
+
+    #[test]
+    fn tenhou_codes_match_the_log_format() {
+        // codes as documented for tenhou.net/6 logs
+        let documented = [
+            ("1m", false, 11),
+            ("9m", false, 19),
+            ("5p", false, 25),
+            ("7s", false, 37),
+            ("1z", false, 41),
+            ("7z", false, 47),
+            ("5m", true, 51),
+            ("5p", true, 52),
+            ("5s", true, 53),
+        ];
+        for (notation, is_red, code) in documented {
+            assert_eq!(tenhou_code(&tile(notation), is_red), code);
+            assert_eq!(from_tenhou_code(code), Some((tile(notation), is_red)));
+        }
+        for unused in [0, 10, 20, 30, 40, 48, 50, 54] {
+            assert_eq!(from_tenhou_code(unused), None);
+        }
+    }
+
+    #[test]
+    fn tenhou_query_round_trips() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        assert_eq!(to_tenhou_query(&input), "q=234567m345678p44s");
+
+        // one red five goes to the first suit that has a five
+        input.game_context.num_akadora = 2;
+        let query = to_tenhou_query(&input);
+        assert_eq!(query, "q=234067m340678p44s");
+
+        // reading it back (0 = red 5) gives the same tiles
+        let plain = query.trim_start_matches("q=").replace('0', "5");
+        let mut tiles = all_tiles(&input);
+        tiles.sort_unstable_by_key(tile_to_index);
+        assert_eq!(parse_tiles(&plain).unwrap(), tiles);
+    }
+
+    #[test]
+    fn tenhou_query_writes_kans_as_three_tiles() {
+        let mut input = hand("345p234s55s", "5s", true);
+        input.closed_kans = vec![tile("1m")];
+        input.open_melds = vec![OpenMeldInput::kan(tile("9p"))];
+        let query = to_tenhou_query(&input);
+        assert_eq!(query, "q=111m345999p23455s");
+        assert_eq!(
+            parse_tiles(query.trim_start_matches("q=")).unwrap().len(),
+            14
+        );
+    }
+
+    #[test]
+    fn tenhou_query_lists_honors_last() {
+        let input = hand("111z123m456p789s22z", "2z", false);
+        assert_eq!(to_tenhou_query(&input), "q=123m456p789s11122z");
+    }
+}
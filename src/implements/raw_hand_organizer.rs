@@ -1,12 +1,14 @@
 // raw_hand_organizer.rs: Organizes a raw hand input into standard melds and pair
 
+use super::shanten_calculator::hand_size;
 use super::types::{
     game::AgariType,
-    hand::{AgariHand, HandOrganization, Machi, Mentsu, MentsuType},
+    hand::{AgariHand, HandOrganization, Machi, Mentsu, MentsuType, WinGroup},
     input::UserInput,
-    tiles::{Hai, index_to_tile, tile_to_index},
+    tiles::{Hai, index_to_tile, tile_to_index, try_tile_to_index},
 };
-use std::convert::TryInto;
+use alloc::vec::Vec;
+use core::convert::TryInto;
 
 // Recursive Parsing
 mod recursive_parser {
@@ -30,6 +32,7 @@ mod recursive_parser {
                 mentsu_type: MentsuType::Koutsu,
                 is_minchou: false, // is_open
                 tiles: [tile, tile, tile, tile],
+                called_from: None,
             });
 
             if find_mentsu_recursive(counts, mentsu) {
@@ -53,6 +56,7 @@ mod recursive_parser {
                 mentsu_type: MentsuType::Shuntsu,
                 is_minchou: false,
                 tiles: [tile1, tile2, tile3, tile3],
+                called_from: None,
             });
 
             if find_mentsu_recursive(counts, mentsu) {
@@ -67,12 +71,95 @@ mod recursive_parser {
 
         false
     }
+
+    /// Every way to split the counts into melds (lowest tile first, so no duplicates)
+    pub fn find_all_mentsu(
+        counts: &mut [u8; 34],
+        mentsu: &mut Vec<Mentsu>,
+        results: &mut Vec<Vec<Mentsu>>,
+    ) {
+        let mut i = 0;
+        while i < 34 && counts[i] == 0 {
+            i += 1;
+        }
+        if i == 34 {
+            results.push(mentsu.clone());
+            return;
+        }
+
+        // Koutsu
+        if counts[i] >= 3 {
+            let tile = index_to_tile(i);
+            counts[i] -= 3;
+            mentsu.push(Mentsu {
+                mentsu_type: MentsuType::Koutsu,
+                is_minchou: false,
+                tiles: [tile, tile, tile, tile],
+                called_from: None,
+            });
+            find_all_mentsu(counts, mentsu, results);
+            mentsu.pop();
+            counts[i] += 3;
+        }
+
+        // Shuntsu
+        if i < 27 && (i % 9) < 7 && counts[i + 1] > 0 && counts[i + 2] > 0 {
+            let tile3 = index_to_tile(i + 2);
+            counts[i] -= 1;
+            counts[i + 1] -= 1;
+            counts[i + 2] -= 1;
+            mentsu.push(Mentsu {
+                mentsu_type: MentsuType::Shuntsu,
+                is_minchou: false,
+                tiles: [index_to_tile(i), index_to_tile(i + 1), tile3, tile3],
+                called_from: None,
+            });
+            find_all_mentsu(counts, mentsu, results);
+            mentsu.pop();
+            counts[i] += 1;
+            counts[i + 1] += 1;
+            counts[i + 2] += 1;
+        }
+    }
+
+    /// find_all_mentsu, counting the splits instead of collecting them
+    pub fn count_all_mentsu(counts: &mut [u8; 34]) -> usize {
+        let mut i = 0;
+        while i < 34 && counts[i] == 0 {
+            i += 1;
+        }
+        if i == 34 {
+            return 1;
+        }
+
+        let mut total = 0;
+        if counts[i] >= 3 {
+            counts[i] -= 3;
+            total += count_all_mentsu(counts);
+            counts[i] += 3;
+        }
+        if i < 27 && (i % 9) < 7 && counts[i + 1] > 0 && counts[i + 2] > 0 {
+            counts[i] -= 1;
+            counts[i + 1] -= 1;
+            counts[i + 2] -= 1;
+            total += count_all_mentsu(counts);
+            counts[i] += 1;
+            counts[i + 1] += 1;
+            counts[i + 2] += 1;
+        }
+        total
+    }
 }
 
 // Wait Type Analysis
 mod wait_analyzer {
     use super::*;
 
+    // declared melds (calls and kans) were complete before the win
+    fn can_complete(mentsu: &Mentsu) -> bool {
+        !mentsu.is_minchou && mentsu.mentsu_type != MentsuType::Kantsu
+    }
+
     fn mentsu_contains_tile(mentsu: &Mentsu, tile: &Hai) -> bool {
         match mentsu.mentsu_type {
             MentsuType::Koutsu | MentsuType::Kantsu => mentsu.tiles[0] == *tile,
@@ -82,15 +169,54 @@ mod wait_analyzer {
         }
     }
 
-    pub fn determine_wait_type(mentsu: &[Mentsu; 4], atama: (Hai, Hai), agari_hai: Hai) -> Machi {
+    pub fn find_winning_group(
+        mentsu: &[Mentsu; 4],
+        atama: (Hai, Hai),
+        agari_hai: Hai,
+    ) -> Option<WinGroup> {
         if agari_hai == atama.0 {
-            return Machi::Tanki;
+            return Some(WinGroup::Pair);
         }
 
-        let winning_meld = mentsu
+        mentsu
             .iter()
-            .find(|m| mentsu_contains_tile(m, &agari_hai))
-            .expect("Winning tile not in pair or melds. Invalid hand.");
+            .position(|m| can_complete(m) && mentsu_contains_tile(m, &agari_hai))
+            .map(WinGroup::Meld)
+    }
+
+    pub fn determine_wait_type(
+        mentsu: &[Mentsu; 4],
+        atama: (Hai, Hai),
+        agari_hai: Hai,
+    ) -> Result<Machi, &'static str> {
+        let group = find_winning_group(mentsu, atama, agari_hai)
+            .ok_or("Invalid hand: winning tile is not in the pair or any concealed meld.")?;
+        Ok(wait_for_group(mentsu, group, agari_hai))
+    }
+
+    /// Every group the winning tile could have completed
+    pub fn all_winning_groups(
+        mentsu: &[Mentsu; 4],
+        atama: (Hai, Hai),
+        agari_hai: Hai,
+    ) -> Vec<WinGroup> {
+        let mut groups = Vec::new();
+        if agari_hai == atama.0 {
+            groups.push(WinGroup::Pair);
+        }
+        for (i, m) in mentsu.iter().enumerate() {
+            if can_complete(m) && mentsu_contains_tile(m, &agari_hai) {
+                groups.push(WinGroup::Meld(i));
+            }
+        }
+        groups
+    }
+
+    pub fn wait_for_group(mentsu: &[Mentsu; 4], group: WinGroup, agari_hai: Hai) -> Machi {
+        let winning_meld = match group {
+            WinGroup::Pair => return Machi::Tanki,
+            WinGroup::Meld(i) => &mentsu[i],
+        };
 
         match winning_meld.mentsu_type {
             MentsuType::Koutsu | MentsuType::Kantsu => Machi::Shanpon,
@@ -99,16 +225,18 @@ mod wait_analyzer {
                 let t2 = winning_meld.tiles[1];
                 let t3 = winning_meld.tiles[2];
 
+                // Compare tile numbers, not indices, so suit boundaries can't matter
+                // (a sequence never spans suits: the parser only starts them at 1-7)
                 if agari_hai == t2 {
                     Machi::Kanchan
                 } else if agari_hai == t1 {
-                    if tile_to_index(&t3) % 9 == 8 {
+                    if matches!(t3, Hai::Suhai(9, _)) {
                         Machi::Penchan
                     } else {
                         Machi::Ryanmen
                     }
                 } else if agari_hai == t3 {
-                    if tile_to_index(&t1) % 9 == 0 {
+                    if matches!(t1, Hai::Suhai(1, _)) {
                         Machi::Penchan
                     } else {
                         Machi::Ryanmen
@@ -121,10 +249,39 @@ mod wait_analyzer {
     }
 }
 
-pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str> {
+/// Group completed by the winning tile, consistent with hand.machi.
+/// Every hand from organize_hand has one. A hand built by hand whose winning tile
+/// is in no concealed group can only be a tanki wait, so that gives Pair.
+pub fn winning_group(hand: &AgariHand) -> WinGroup {
+    wait_analyzer::all_winning_groups(&hand.mentsu, hand.atama, hand.agari_hai)
+        .into_iter()
+        .find(|g| wait_analyzer::wait_for_group(&hand.mentsu, *g, hand.agari_hai) == hand.machi)
+        .or_else(|| wait_analyzer::find_winning_group(&hand.mentsu, hand.atama, hand.agari_hai))
+        .unwrap_or(WinGroup::Pair)
+}
+
+// concealed tile counts (plus a Ron winning tile) and the declared kans/melds
+fn count_tiles_and_declared_melds(
+    input: &UserInput,
+) -> Result<([u8; 34], Vec<Mentsu>), &'static str> {
+    // Every tile must be one of the 34 before it is used as an index
+    let meld_tiles = input.open_melds.iter().map(|m| &m.representative_tile);
+    let mut all = input
+        .hand_tiles
+        .iter()
+        .chain([&input.winning_tile])
+        .chain(meld_tiles)
+        .chain(&input.closed_kans);
+    if all.any(|tile| try_tile_to_index(tile).is_none()) {
+        return Err("Invalid tiles: a numbered tile must be 1-9.");
+    }
+
     let mut master_counts = [0u8; 34];
     for tile in &input.hand_tiles {
         master_counts[tile_to_index(tile)] += 1;
+        if master_counts[tile_to_index(tile)] > 4 {
+            return Err("Invalid hand: more than 4 copies of a tile.");
+        }
     }
 
     // If Ron, the winning tile is not in hand_tiles
@@ -132,7 +289,6 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
         master_counts[tile_to_index(&input.winning_tile)] += 1;
     }
 
-    let concealed_counts = master_counts;
     let mut final_mentsu: Vec<Mentsu> = Vec::with_capacity(4);
 
     // Closed Kans
@@ -142,6 +298,7 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
             mentsu_type: MentsuType::Kantsu,
             is_minchou: false,
             tiles: [kan_tile, kan_tile, kan_tile, kan_tile],
+            called_from: None,
         });
     }
 
@@ -156,6 +313,7 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
                     mentsu_type: MentsuType::Koutsu,
                     is_minchou: true,
                     tiles: [rep_tile, rep_tile, rep_tile, rep_tile],
+                    called_from: meld.called_from,
                 });
             }
             MentsuType::Kantsu => {
@@ -163,6 +321,7 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
                     mentsu_type: MentsuType::Kantsu,
                     is_minchou: true,
                     tiles: [rep_tile, rep_tile, rep_tile, rep_tile],
+                    called_from: meld.called_from,
                 });
             }
             MentsuType::Shuntsu => {
@@ -181,15 +340,34 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
                     mentsu_type: MentsuType::Shuntsu,
                     is_minchou: true,
                     tiles: [t1, t2, t3, t3],
+                    called_from: meld.called_from,
                 });
             }
         }
     }
 
-    let mentsu_needed = 4 - final_mentsu.len();
+    // Each declared meld stands in for 3 concealed tiles (a kan's 4th is a replacement draw)
+    let concealed: usize = master_counts.iter().map(|&c| c as usize).sum();
+    if final_mentsu.len() <= 4 && concealed + 3 * final_mentsu.len() != 14 {
+        return Err("Invalid hand: incomplete hand, the tile count does not match a win.");
+    }
+
+    Ok((master_counts, final_mentsu))
+}
+
+pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str> {
+    let (master_counts, mut final_mentsu) = count_tiles_and_declared_melds(input)?;
+    let concealed_counts = master_counts;
+
+    let mentsu_needed = 4_usize
+        .checked_sub(final_mentsu.len())
+        .ok_or("Invalid hand: more than 4 melds declared.")?;
     let agari_hai = input.winning_tile;
 
-    // 4 known melds
+    // 4 known melds (e.g. four kans, or a fully called hand): only the pair is left.
+    // Declared melds were complete before the win and can never be the group it
+    // finished (see can_complete), so the wait is always a tanki on the pair.
+    // A shanpon would need a fifth copy of the winning tile.
     if mentsu_needed == 0 {
         for i in 0..34 {
             if concealed_counts[i] == 2 {
@@ -210,11 +388,7 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
                 return Ok(HandOrganization::YonmentsuIchiatama(agari_hand));
             }
         }
-        if input.hand_tiles.len() == 14 {
-            // placeholder
-        } else {
-            return Err("Invalid hand: 4 open melds but no pair found.");
-        }
+        return Err("Invalid hand: 4 declared melds but no pair found.");
     }
 
     // Standard Hand
@@ -233,7 +407,8 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
                         .try_into()
                         .expect("Hand parsing logic error: final_mentsu length not 4");
 
-                    let machi = wait_analyzer::determine_wait_type(&mentsu_array, atama, agari_hai);
+                    let machi =
+                        wait_analyzer::determine_wait_type(&mentsu_array, atama, agari_hai)?;
 
                     let agari_hand = AgariHand {
                         mentsu: mentsu_array,
@@ -253,3 +428,369 @@ pub fn organize_hand(input: &UserInput) -> Result<HandOrganization, &'static str
         agari_hai,
     })
 }
+
+/// Every standard reading of the hand: each (melds, pair) split, once per
+/// group the winning tile could have completed. Empty if there is none.
+/// Melds are in tile order, so two readings are equal only if their
+/// melds, pair and wait all match; duplicates are dropped.
+pub fn organize_all(input: &UserInput) -> Result<Vec<AgariHand>, &'static str> {
+    let (concealed_counts, declared) = count_tiles_and_declared_melds(input)?;
+    if declared.len() > 4 {
+        return Err("Invalid hand: more than 4 melds declared.");
+    }
+    let readings = find_readings(&concealed_counts, &declared, input.winning_tile);
+
+    // 4 declared melds (e.g. four kans) leave only the pair
+    if declared.len() == 4 && readings.is_empty() {
+        return Err("Invalid hand: 4 declared melds but no pair found.");
+    }
+
+    Ok(readings)
+}
+
+/// organize_all for a bare 14-tile count array (fully concealed, no game
+/// context), e.g. for shanten or ukeire tools
+pub fn organize_counts(counts: &[u8; 34], agari_hai: Hai) -> Result<Vec<AgariHand>, &'static str> {
+    if counts.iter().any(|&c| c > 4) {
+        return Err("Invalid hand: more than 4 copies of a tile.");
+    }
+    if counts.iter().sum::<u8>() != 14 {
+        return Err("Invalid hand: a concealed hand needs 14 tiles.");
+    }
+    if counts[tile_to_index(&agari_hai)] == 0 {
+        return Err("Invalid winning tile: it is not in the hand.");
+    }
+    Ok(find_readings(counts, &[], agari_hai))
+}
+
+/// Number of distinct (pair, melds) splits of complete concealed counts
+/// (14, 11, 8, ... tiles), ignoring waits; 0 if there is no standard reading
+pub fn decomposition_count(counts: &[u8; 34]) -> usize {
+    if hand_size(counts).is_none_or(|total| total % 3 != 2) {
+        return 0;
+    }
+
+    let mut temp_counts = *counts;
+    let mut count = 0;
+    for i in 0..34 {
+        if temp_counts[i] >= 2 {
+            temp_counts[i] -= 2;
+            count += recursive_parser::count_all_mentsu(&mut temp_counts);
+            temp_counts[i] += 2;
+        }
+    }
+    count
+}
+
+// every (declared + concealed melds, pair, wait) reading of the concealed counts
+fn find_readings(
+    concealed_counts: &[u8; 34],
+    declared: &[Mentsu],
+    agari_hai: Hai,
+) -> Vec<AgariHand> {
+    let mentsu_needed = 4 - declared.len();
+    let mut readings: Vec<AgariHand> = Vec::new();
+
+    for i in 0..34 {
+        if concealed_counts[i] < 2 {
+            continue;
+        }
+        let mut temp_counts = *concealed_counts;
+        temp_counts[i] -= 2;
+        let atama = (index_to_tile(i), index_to_tile(i));
+
+        let mut splits = Vec::new();
+        recursive_parser::find_all_mentsu(&mut temp_counts, &mut Vec::new(), &mut splits);
+
+        for closed_mentsu in splits {
+            if closed_mentsu.len() != mentsu_needed {
+                continue;
+            }
+            let mut all_mentsu = declared.to_vec();
+            all_mentsu.extend(closed_mentsu);
+            all_mentsu.sort_by_key(|m| {
+                (
+                    tile_to_index(&m.tiles[0]),
+                    m.mentsu_type as u8,
+                    m.is_minchou,
+                )
+            });
+            let mentsu: [Mentsu; 4] = all_mentsu
+                .try_into()
+                .expect("Hand parsing logic error: final_mentsu length not 4");
+
+            // One reading per group the winning tile could have completed, so each
+            // placement (e.g. 56+7 ryanmen vs 6_8 kanchan) is scored and the best kept
+            for group in wait_analyzer::all_winning_groups(&mentsu, atama, agari_hai) {
+                let hand = AgariHand {
+                    mentsu,
+                    atama,
+                    agari_hai,
+                    machi: wait_analyzer::wait_for_group(&mentsu, group, agari_hai),
+                };
+                if !readings.contains(&hand) {
+                    readings.push(hand);
+                }
+            }
+        }
+    }
+    readings
+}
+
+/// All readings to score: every standard reading, then the irregular one
+/// (Chiitoitsu / Kokushi, or the "no standard reading" error path)
+pub fn organize_candidates(input: &UserInput) -> Result<Vec<HandOrganization>, &'static str> {
+    let (master_counts, _) = count_tiles_and_declared_melds(input)?;
+    let mut candidates: Vec<HandOrganization> = organize_all(input)?
+        .into_iter()
+        .map(HandOrganization::YonmentsuIchiatama)
+        .collect();
+
+    candidates.push(HandOrganization::Irregular {
+        counts: master_counts,
+        agari_hai: input.winning_tile,
+    });
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::input::OpenMeldInput;
+    use crate::implements::test_hands::{counts, hand, tile};
+    use crate::implements::types::tiles::{Kaze, Suhai};
+    use crate::implements::{calculate_agari, yaku::Yaku};
+    use alloc::vec;
+
+    #[test]
+    fn malformed_tiles_are_an_error() {
+        for bad in [Hai::Suhai(0, Suhai::Manzu), Hai::Suhai(12, Suhai::Souzu)] {
+            let mut input = hand("234567m345678p44s", "8p", true);
+            input.hand_tiles[0] = bad;
+            assert_eq!(
+                organize_hand(&input).err(),
+                Some("Invalid tiles: a numbered tile must be 1-9.")
+            );
+
+            let mut input = hand("234567m345678p44s", "8p", false);
+            input.winning_tile = bad;
+            assert!(organize_hand(&input).is_err());
+        }
+    }
+
+    // the fuzz target's property over a fixed spread of byte strings
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_inputs_do_not_panic() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        for seed in 0..4000u32 {
+            let bytes: Vec<u8> = (0..200u32)
+                .map(|i| {
+                    (seed.wrapping_mul(2_654_435_761) ^ i.wrapping_mul(40_503)).to_le_bytes()[1]
+                })
+                .collect();
+            if let Ok(input) = UserInput::arbitrary(&mut Unstructured::new(&bytes)) {
+                let _ = organize_hand(&input);
+            }
+        }
+    }
+
+    #[test]
+    fn shanpon_win_points_at_the_triplet() {
+        let input = hand("123m22m345p678s999s", "9s", false);
+        let Ok(HandOrganization::YonmentsuIchiatama(agari)) = organize_hand(&input) else {
+            panic!("expected a standard hand");
+        };
+        assert_eq!(agari.machi, Machi::Shanpon);
+
+        let WinGroup::Meld(i) = winning_group(&agari) else {
+            panic!("expected a meld");
+        };
+        assert_eq!(agari.mentsu[i].mentsu_type, MentsuType::Koutsu);
+        assert_eq!(agari.mentsu[i].tiles[0], tile("9s"));
+    }
+
+    #[test]
+    fn tanki_win_points_at_the_pair() {
+        let input = hand("123m456m345p678s99s", "9s", false);
+        let Ok(HandOrganization::YonmentsuIchiatama(agari)) = organize_hand(&input) else {
+            panic!("expected a standard hand");
+        };
+        assert_eq!(winning_group(&agari), WinGroup::Pair);
+    }
+
+    #[test]
+    fn decomposition_count_rejects_impossible_counts() {
+        let mut counts = [0u8; 34];
+        counts[0] = 255;
+        counts[1] = 2;
+        assert_eq!(decomposition_count(&counts), 0);
+    }
+
+    #[test]
+    fn organize_all_returns_every_distinct_reading() {
+        // 111222333m reads as three triplets or as three 123m runs
+        let readings = organize_all(&hand("111222333m456p77s", "7s", false)).unwrap();
+        assert!(readings.len() >= 2, "{readings:?}");
+        for (i, a) in readings.iter().enumerate() {
+            assert!(readings[i + 1..].iter().all(|b| a != b), "duplicate {a:?}");
+        }
+
+        let is_koutsu = |r: &AgariHand| {
+            r.mentsu
+                .iter()
+                .filter(|m| m.mentsu_type == MentsuType::Koutsu)
+                .count()
+        };
+        assert!(readings.iter().any(|r| is_koutsu(r) == 3));
+        assert!(readings.iter().any(|r| is_koutsu(r) == 0));
+    }
+
+    #[test]
+    fn four_closed_kans_leave_only_the_pair() {
+        let mut input = hand("66z", "6z", true);
+        input.closed_kans = vec![tile("1m"), tile("5p"), tile("9s"), tile("2z")];
+
+        let Ok(HandOrganization::YonmentsuIchiatama(agari)) = organize_hand(&input) else {
+            panic!("four kans and a pair is a standard hand");
+        };
+        assert!(
+            agari
+                .mentsu
+                .iter()
+                .all(|m| m.mentsu_type == MentsuType::Kantsu)
+        );
+        assert_eq!(agari.atama, (tile("6z"), tile("6z")));
+        assert_eq!(agari.machi, Machi::Tanki);
+
+        // Scored end to end as yakuman
+        let result = calculate_agari(&input).unwrap();
+        assert!(result.has_yaku(Yaku::Suukantsu));
+        assert!(result.has_yaku(Yaku::SuuankouTanki));
+        assert!(result.is_yakuman());
+    }
+
+    #[test]
+    fn organize_counts_matches_organize_hand() {
+        for (all, win) in [("234567m345678p44s", "8p"), ("111222333m456p77s", "7s")] {
+            let input = hand(all, win, true);
+            let readings = organize_counts(&counts(all), tile(win)).unwrap();
+            assert_eq!(readings, organize_all(&input).unwrap(), "{all}");
+
+            let Ok(HandOrganization::YonmentsuIchiatama(agari)) = organize_hand(&input) else {
+                panic!("{all} is a standard hand");
+            };
+            assert!(readings.contains(&agari), "{all}");
+        }
+    }
+
+    #[test]
+    fn called_from_survives_organizing() {
+        let mut input = hand("567m345678p44s", "8p", false);
+        input.open_melds = vec![OpenMeldInput {
+            called_from: Some(Kaze::Shaa),
+            ..OpenMeldInput::chi(tile("2m"))
+        }];
+        input.player_context.is_menzen = false;
+
+        let Ok(HandOrganization::YonmentsuIchiatama(agari)) = organize_hand(&input) else {
+            panic!("a standard open hand");
+        };
+        let called: Vec<_> = agari.mentsu.iter().filter(|m| m.is_minchou).collect();
+        assert_eq!(called.len(), 1);
+        assert_eq!(called[0].called_from, Some(Kaze::Shaa));
+        assert!(
+            agari
+                .mentsu
+                .iter()
+                .filter(|m| !m.is_minchou)
+                .all(|m| m.called_from.is_none())
+        );
+    }
+
+    #[test]
+    fn short_hands_are_incomplete() {
+        let err = Some("Invalid hand: incomplete hand, the tile count does not match a win.");
+
+        // 13 tiles is tenpai, not a win: Tsumo with the winning tile left out
+        let mut thirteen = hand("234567m345678p44s", "8p", true);
+        thirteen.hand_tiles.pop();
+        assert_eq!(thirteen.hand_tiles.len(), 13);
+        assert_eq!(organize_hand(&thirteen).err(), err);
+
+        // 10 tiles counting the Ron winning tile
+        let ten = hand("234567m3456p", "6p", false);
+        assert_eq!(organize_hand(&ten).err(), err);
+    }
+
+    #[test]
+    fn edge_waits_at_the_suit_boundaries() {
+        let machi = |all: &str, win: &str| match organize_hand(&hand(all, win, false)) {
+            Ok(HandOrganization::YonmentsuIchiatama(agari)) => agari.machi,
+            _ => panic!("{all} is a standard hand"),
+        };
+
+        // 789m sits right before 1p, 123s right after 9p
+        assert_eq!(machi("789m123p456s789s11z", "7m"), Machi::Penchan);
+        assert_eq!(machi("789m123p456s789s11z", "9m"), Machi::Ryanmen);
+        assert_eq!(machi("234m789p123s456s11z", "3s"), Machi::Penchan);
+        assert_eq!(machi("234m789p123s456s11z", "1s"), Machi::Ryanmen);
+        assert_eq!(machi("789m456p123s789s11z", "5p"), Machi::Kanchan);
+    }
+
+    #[test]
+    fn each_open_meld_constructor_is_accepted() {
+        let melds = [
+            (OpenMeldInput::pon(tile("7z")), MentsuType::Koutsu),
+            (OpenMeldInput::chi(tile("2m")), MentsuType::Shuntsu),
+            (OpenMeldInput::kan(tile("9p")), MentsuType::Kantsu),
+        ];
+        for (meld, mentsu_type) in melds {
+            let mut input = hand("567m345678p44s", "8p", false);
+            input.open_melds = vec![meld.clone()];
+            input.player_context.is_menzen = false;
+
+            let Ok(HandOrganization::YonmentsuIchiatama(agari)) = organize_hand(&input) else {
+                panic!("{meld:?} should organize");
+            };
+            let called: Vec<_> = agari.mentsu.iter().filter(|m| m.is_minchou).collect();
+            assert_eq!(called.len(), 1, "{meld:?}");
+            assert_eq!(called[0].mentsu_type, mentsu_type);
+            assert_eq!(called[0].tiles[0], meld.tiles()[0]);
+        }
+    }
+
+    #[test]
+    fn fully_called_hand_is_a_tanki_wait() {
+        let mut input = hand("44s", "4s", false);
+        input.open_melds = vec![
+            OpenMeldInput::chi(tile("2m")),
+            OpenMeldInput::chi(tile("5p")),
+            OpenMeldInput::pon(tile("9s")),
+            OpenMeldInput::pon(tile("7z")),
+        ];
+        input.player_context.is_menzen = false;
+
+        let Ok(HandOrganization::YonmentsuIchiatama(agari)) = organize_hand(&input) else {
+            panic!("four called melds and a pair is a standard hand");
+        };
+        assert_eq!(agari.atama, (tile("4s"), tile("4s")));
+        assert_eq!(agari.machi, Machi::Tanki);
+
+        // 20 + 2 tanki + 4 open 9s pon + 4 open chun pon = 30
+        let result = calculate_agari(&input).unwrap();
+        assert_eq!(result.fu, 30);
+    }
+
+    #[test]
+    fn decomposition_count_counts_every_split() {
+        // triplets or three runs
+        assert_eq!(decomposition_count(&counts("111222333m456p77s")), 2);
+        assert_eq!(decomposition_count(&counts("11122233344455m")), 4);
+        assert_eq!(decomposition_count(&counts("234567m345678p44s")), 1);
+        // 13 tiles and a hand with no reading
+        assert_eq!(decomposition_count(&counts("1112223334445m")), 0);
+        assert_eq!(decomposition_count(&counts("1155m2277p3399s11z")), 0);
+    }
+}
@@ -0,0 +1,88 @@
+// score_cache.rs: Memoized scoring for repeated identical inputs (cache feature)
+
+use super::calculate_agari_with_rules;
+use super::types::{input::UserInput, rules::Ruleset, scoring::AgariResult};
+use std::collections::HashMap;
+
+/// Remembers the result of each (input, ruleset) pair it has scored.
+/// The Ruleset is part of the key: the same hand can score differently under other rules.
+#[derive(Debug, Default)]
+pub struct ScoreCache {
+    entries: HashMap<(UserInput, Ruleset), Result<AgariResult, &'static str>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl ScoreCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same result as calculate_agari_with_rules, computed once per distinct input
+    pub fn score(
+        &mut self,
+        input: &UserInput,
+        rules: &Ruleset,
+    ) -> Result<AgariResult, &'static str> {
+        let key = (input.clone(), *rules);
+        if let Some(result) = self.entries.get(&key) {
+            self.hits += 1;
+            return result.clone();
+        }
+
+        self.misses += 1;
+        let result = calculate_agari_with_rules(input, rules);
+        self.entries.insert(key, result.clone());
+        result
+    }
+
+    // lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    // lookups that had to be scored
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::test_hands::hand;
+
+    #[test]
+    fn a_repeated_input_is_a_cache_hit() {
+        let mut cache = ScoreCache::new();
+        let rules = Ruleset::default();
+        let input = hand("234567m345678p44s", "8p", false);
+
+        let first = cache.score(&input, &rules);
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        let second = cache.score(&input, &rules);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert_eq!(first, second);
+        assert_eq!(first, calculate_agari_with_rules(&input, &rules));
+
+        // a different ruleset is a different key
+        let kiriage = Ruleset {
+            kiriage_mangan: true,
+            ..rules
+        };
+        cache.score(&input, &kiriage).unwrap();
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 2, 2));
+    }
+}
@@ -3,11 +3,13 @@
 use super::types::{
     game::{AgariType, GameContext, PlayerContext},
     hand::{HandStructure, Machi, MentsuType},
-    scoring::{AgariResult, HandLimit},
-    tiles::{Hai, Jihai},
-    yaku::Yaku,
+    rules::{DoubleWindPair, PinfuTsumo, Ruleset},
+    scoring::{AgariResult, Analysis, HandLimit},
+    tiles::{Hai, Jihai, Kaze},
+    yaku::{Yaku, YakuInfo},
 };
 use super::yaku_checker::YakuResult;
+use alloc::{vec, vec::Vec};
 
 // will be called by gui.rs
 pub fn calculate_score(
@@ -15,47 +17,28 @@ pub fn calculate_score(
     player: &PlayerContext,
     game: &GameContext,
     agari_type: AgariType,
+    rules: &Ruleset,
 ) -> AgariResult {
-    let tsumo_bonus = game.honba as u32 * 100;
-    let ron_bonus = game.honba as u32 * 300;
-    let yaku_list = yaku_result.yaku_list;
+    let honba_bonus = honba_bonus(agari_type, game.honba);
+    let mut yaku_list = yaku_result.yaku_list;
+    sort_yaku_list(&mut yaku_list, player.is_menzen);
     let num_akadora = yaku_result.num_akadora;
 
     // Check for Yakuman
-    let num_yakuman = count_yakuman(&yaku_list);
+    let num_yakuman = count_yakuman(&yaku_list, rules);
 
     if num_yakuman > 0 {
         let han = 13 * num_yakuman as u8;
         let fu = 0;
-        let limit_name = Some(HandLimit::Yakuman);
-        let base_yakuman_points = (8000 * num_yakuman) as u32;
+        let limit_name = Some(match num_yakuman {
+            2 => HandLimit::DoubleYakuman,
+            3 => HandLimit::TripleYakuman,
+            _ => HandLimit::Yakuman,
+        });
+        let base_yakuman_points = 8000 * num_yakuman;
 
         let (base_points, oya_payment, ko_payment, total_payment) =
-            match (player.is_oya, agari_type) {
-                // Oya Tsumo
-                (true, AgariType::Tsumo) => {
-                    let p = round_up_100(base_yakuman_points * 2);
-                    let total = (p + tsumo_bonus) * 3;
-                    (p, p, 0, total)
-                }
-                // Ko Tsumo
-                (false, AgariType::Tsumo) => {
-                    let oya_p = round_up_100(base_yakuman_points * 2);
-                    let ko_p = round_up_100(base_yakuman_points * 1);
-                    let total = (oya_p + tsumo_bonus) + (ko_p + tsumo_bonus) * 2;
-                    (ko_p, oya_p, ko_p, total)
-                }
-                // Oya Ron
-                (true, AgariType::Ron) => {
-                    let total = round_up_100(base_yakuman_points * 6) + ron_bonus;
-                    (total, 0, 0, total)
-                }
-                // Ko Ron
-                (false, AgariType::Ron) => {
-                    let total = round_up_100(base_yakuman_points * 4) + ron_bonus;
-                    (total, 0, 0, total)
-                }
-            };
+            payments(base_yakuman_points, player.is_oya, agari_type, game.honba);
 
         return AgariResult {
             han,
@@ -68,8 +51,12 @@ pub fn calculate_score(
             ko_payment,
             total_payment,
             honba: game.honba,
+            honba_bonus,
+            riichi_bou: game.riichi_bou,
             agari_type,
             is_oya: player.is_oya,
+            is_menzen: player.is_menzen,
+            structure: Some(yaku_result.hand_structure),
         };
     }
 
@@ -81,12 +68,79 @@ pub fn calculate_score(
         player,
         game,
         agari_type,
+        rules,
     );
 
-    let (basic_points, limit_name) = calculate_basic_points(han, fu);
+    let (basic_points, limit_name) = calculate_basic_points(han, fu, rules);
 
     // Calculate Final Payments
-    let (base_points, oya_payment, ko_payment, total_payment) = match (player.is_oya, agari_type) {
+    let (base_points, oya_payment, ko_payment, total_payment) =
+        payments(basic_points, player.is_oya, agari_type, game.honba);
+
+    AgariResult {
+        han,
+        fu,
+        yaku_list,
+        num_akadora,
+        limit_name,
+        base_points,
+        oya_payment,
+        ko_payment,
+        total_payment,
+        honba: game.honba,
+        honba_bonus,
+        riichi_bou: game.riichi_bou,
+        agari_type,
+        is_oya: player.is_oya,
+        is_menzen: player.is_menzen,
+        structure: Some(yaku_result.hand_structure),
+    }
+}
+
+/// Payments for a known han and fu, with no hand behind them (default rules).
+/// The result has an empty yaku list, no riichi sticks, is_menzen false and no structure.
+pub fn points_from_han_fu(
+    han: u8,
+    fu: u8,
+    is_oya: bool,
+    agari_type: AgariType,
+    honba: u8,
+) -> AgariResult {
+    let (basic_points, limit_name) = calculate_basic_points(han, fu, &Ruleset::default());
+    let (base_points, oya_payment, ko_payment, total_payment) =
+        payments(basic_points, is_oya, agari_type, honba);
+
+    AgariResult {
+        han,
+        fu,
+        yaku_list: Vec::new(),
+        num_akadora: 0,
+        limit_name,
+        base_points,
+        oya_payment,
+        ko_payment,
+        total_payment,
+        honba,
+        honba_bonus: honba_bonus(agari_type, honba),
+        riichi_bou: 0,
+        agari_type,
+        is_oya,
+        is_menzen: false,
+        structure: None,
+    }
+}
+
+// (base_points, oya_payment, ko_payment, total_payment) for the basic points
+fn payments(
+    basic_points: u32,
+    is_oya: bool,
+    agari_type: AgariType,
+    honba: u8,
+) -> (u32, u32, u32, u32) {
+    let tsumo_bonus = honba as u32 * 100;
+    let ron_bonus = honba as u32 * 300;
+
+    match (is_oya, agari_type) {
         // Oya Tsumo
         (true, AgariType::Tsumo) => {
             let p = round_up_100(basic_points * 2);
@@ -96,7 +150,7 @@ pub fn calculate_score(
         // Ko Tsumo
         (false, AgariType::Tsumo) => {
             let oya_p = round_up_100(basic_points * 2);
-            let ko_p = round_up_100(basic_points * 1);
+            let ko_p = round_up_100(basic_points);
             let total = (oya_p + tsumo_bonus) + (ko_p + tsumo_bonus) * 2;
             (ko_p, oya_p, ko_p, total)
         }
@@ -110,21 +164,224 @@ pub fn calculate_score(
             let total = round_up_100(basic_points * 4) + ron_bonus;
             (total, 0, 0, total)
         }
+    }
+}
+
+// honba part of total_payment
+fn honba_bonus(agari_type: AgariType, honba: u8) -> u32 {
+    match agari_type {
+        AgariType::Tsumo => honba as u32 * 100 * 3, // paid by each of the 3 other players
+        AgariType::Ron => honba as u32 * 300,
+    }
+}
+
+/// Points the winner gains: every payment (honba included) plus the riichi sticks
+pub fn score_value(result: &AgariResult) -> u32 {
+    result.total_payment + result.riichi_bou as u32 * 1000
+}
+
+/// How many more points the winner gains with a than with b (negative if b is worth more)
+pub fn score_delta(a: &AgariResult, b: &AgariResult) -> i64 {
+    score_value(a) as i64 - score_value(b) as i64
+}
+
+/// Each seat's change in points from a Tsumo win, in seat order (East first).
+/// Honba and riichi sticks come from result; the winner's gain includes both,
+/// so the four deltas add up to the sticks taken off the table.
+/// Err if result is not a Tsumo, or if winner disagrees with result.is_oya
+/// (the dealer sits East), since the payer split would be wrong.
+pub fn tsumo_deltas(result: &AgariResult, winner: Kaze) -> Result<[(Kaze, i32); 4], &'static str> {
+    if result.agari_type != AgariType::Tsumo {
+        return Err("Invalid tsumo deltas: the result is not a Tsumo win.");
+    }
+    if result.is_oya != (winner == Kaze::Ton) {
+        return Err("Invalid tsumo deltas: the dealer (is_oya) must be the East seat.");
+    }
+
+    let tsumo_bonus = result.honba as i32 * 100;
+    let mut deltas = Kaze::all().map(|seat| {
+        let payment = if seat == winner {
+            0
+        } else if result.is_oya || seat == Kaze::Ton {
+            // everyone pays the dealer's rate when the dealer wins
+            result.oya_payment as i32 + tsumo_bonus
+        } else {
+            result.ko_payment as i32 + tsumo_bonus
+        };
+        (seat, -payment)
+    });
+
+    // The winner collects every payment and the sticks on the table
+    let collected: i32 = deltas.iter().map(|(_, delta)| -delta).sum();
+    let sticks = result.riichi_bou as i32 * 1000;
+    for (seat, delta) in deltas.iter_mut() {
+        if *seat == winner {
+            *delta = collected + sticks;
+        }
+    }
+    Ok(deltas)
+}
+
+/// Han of a yaku list: 13 per yakuman, otherwise the yaku and dora han
+pub fn count_han(yaku_list: &[Yaku], is_menzen: bool, rules: &Ruleset) -> u8 {
+    match count_yakuman(yaku_list, rules) {
+        0 => calculate_han(yaku_list, is_menzen),
+        n => 13 * n as u8,
+    }
+}
+
+/// Han from the hand itself: count_han without the table-state yaku.
+/// Excluded: Riichi, DaburuRiichi, OpenRiichi, Ippatsu, MenzenTsumo, HaiteiRaoyue,
+/// HouteiRaoyui, RinshanKaihou, Chankan, Tenhou, Chiihou, Renhou, Dora, UraDora and AkaDora.
+/// rules should be the ruleset the result was scored under (it caps stacked yakuman).
+pub fn intrinsic_han(result: &AgariResult, is_menzen: bool, rules: &Ruleset) -> u8 {
+    let hand_yaku: Vec<Yaku> = result
+        .yaku_list
+        .iter()
+        .copied()
+        .filter(|yaku| !is_situational(yaku))
+        .collect();
+    count_han(&hand_yaku, is_menzen, rules)
+}
+
+/// Every yaku (and the three kinds of dora, 1 han each) with its han, for reference tables
+pub fn all_yaku() -> &'static [YakuInfo] {
+    ALL_YAKU
+}
+
+// han come from the same functions scoring uses
+static ALL_YAKU: &[YakuInfo] = &[
+    yaku_info(Yaku::Riichi, "Riichi", "立直"),
+    yaku_info(Yaku::OpenRiichi, "Open Riichi", "オープン立直"),
+    yaku_info(Yaku::Ippatsu, "Ippatsu", "一発"),
+    yaku_info(Yaku::MenzenTsumo, "Fully Concealed Hand", "門前清自摸和"),
+    yaku_info(Yaku::Pinfu, "No-Points Hand", "平和"),
+    yaku_info(Yaku::Iipeikou, "Pure Double Sequence", "一盃口"),
+    yaku_info(Yaku::HaiteiRaoyue, "Under the Sea", "海底撈月"),
+    yaku_info(Yaku::HouteiRaoyui, "Under the River", "河底撈魚"),
+    yaku_info(Yaku::RinshanKaihou, "After a Kan", "嶺上開花"),
+    yaku_info(Yaku::Chankan, "Robbing a Kan", "搶槓"),
+    yaku_info(Yaku::Tanyao, "All Simples", "断幺九"),
+    yaku_info(Yaku::YakuhaiJikaze, "Seat Wind", "役牌 自風"),
+    yaku_info(Yaku::YakuhaiBakaze, "Prevalent Wind", "役牌 場風"),
+    yaku_info(Yaku::YakuhaiSangenpai, "Dragon", "役牌 三元牌"),
+    yaku_info(Yaku::DaburuRiichi, "Double Riichi", "ダブル立直"),
+    yaku_info(Yaku::Chiitoitsu, "Seven Pairs", "七対子"),
+    yaku_info(Yaku::SanshokuDoujun, "Mixed Triple Sequence", "三色同順"),
+    yaku_info(Yaku::Ittsu, "Pure Straight", "一気通貫"),
+    yaku_info(Yaku::Chanta, "Half Outside Hand", "全帯幺九"),
+    yaku_info(Yaku::Toitoi, "All Triplets", "対々和"),
+    yaku_info(Yaku::Sanankou, "Three Concealed Triplets", "三暗刻"),
+    yaku_info(Yaku::SanshokuDoukou, "Triple Triplets", "三色同刻"),
+    yaku_info(Yaku::Sankantsu, "Three Quads", "三槓子"),
+    yaku_info(Yaku::Shousangen, "Little Three Dragons", "小三元"),
+    yaku_info(Yaku::Honroutou, "All Terminals and Honors", "混老頭"),
+    yaku_info(Yaku::Sanrenkou, "Three Consecutive Triplets", "三連刻"),
+    yaku_info(Yaku::Ryanpeikou, "Twice Pure Double Sequence", "二盃口"),
+    yaku_info(Yaku::Junchan, "Fully Outside Hand", "純全帯幺九"),
+    yaku_info(Yaku::Honitsu, "Half Flush", "混一色"),
+    yaku_info(Yaku::Chinitsu, "Full Flush", "清一色"),
+    yaku_info(Yaku::Tenhou, "Blessing of Heaven", "天和"),
+    yaku_info(Yaku::Chiihou, "Blessing of Earth", "地和"),
+    yaku_info(Yaku::Renhou, "Blessing of Man", "人和"),
+    yaku_info(Yaku::Daisangen, "Big Three Dragons", "大三元"),
+    yaku_info(Yaku::Suuankou, "Four Concealed Triplets", "四暗刻"),
+    yaku_info(Yaku::Daisuushi, "Four Big Winds", "大四喜"),
+    yaku_info(Yaku::Shousuushi, "Four Little Winds", "小四喜"),
+    yaku_info(Yaku::Tsuuiisou, "All Honors", "字一色"),
+    yaku_info(Yaku::Chinroutou, "All Terminals", "清老頭"),
+    yaku_info(Yaku::Ryuuiisou, "All Green", "緑一色"),
+    yaku_info(Yaku::Suukantsu, "Four Quads", "四槓子"),
+    yaku_info(Yaku::KokushiMusou, "Thirteen Orphans", "国士無双"),
+    yaku_info(Yaku::ChuurenPoutou, "Nine Gates", "九蓮宝燈"),
+    yaku_info(
+        Yaku::SuuankouTanki,
+        "Single Wait Four Concealed",
+        "四暗刻単騎",
+    ),
+    yaku_info(
+        Yaku::KokushiMusouJusanmen,
+        "13-Sided Wait Thirteen Orphans",
+        "国士無双十三面待ち",
+    ),
+    yaku_info(Yaku::JunseiChuurenPoutou, "True Nine Gates", "純正九蓮宝燈"),
+    yaku_info(Yaku::Daichisei, "Big Seven Stars", "大七星"),
+    yaku_info(Yaku::Suurenkou, "Four Consecutive Triplets", "四連刻"),
+    yaku_info(Yaku::Dora, "Dora", "ドラ"),
+    yaku_info(Yaku::UraDora, "Ura Dora", "裏ドラ"),
+    yaku_info(Yaku::AkaDora, "Red Five Dora", "赤ドラ"),
+];
+
+const fn yaku_info(yaku: Yaku, name_en: &'static str, name_jp: &'static str) -> YakuInfo {
+    let multiplier = yakuman_multiplier(&yaku);
+    let (closed_han, open_han) = if multiplier > 0 {
+        let han = 13 * multiplier as u8;
+        (han, if is_menzen_only(&yaku) { 0 } else { han })
+    } else {
+        (get_han_value(&yaku, true), get_han_value(&yaku, false))
     };
+    YakuInfo {
+        yaku,
+        closed_han,
+        open_han,
+        is_yakuman: multiplier > 0,
+        name_en,
+        name_jp,
+    }
+}
 
-    AgariResult {
-        han,
-        fu,
-        yaku_list,
-        num_akadora,
-        limit_name,
-        base_points,
-        oya_payment,
-        ko_payment,
-        total_payment,
-        honba: game.honba,
+/// Wait, per-yaku han and fu sources behind the score, for explanations
+pub fn analyze_score(
+    yaku_result: &YakuResult,
+    player: &PlayerContext,
+    game: &GameContext,
+    agari_type: AgariType,
+    rules: &Ruleset,
+) -> Analysis {
+    let mut yaku_list = yaku_result.yaku_list.clone();
+    sort_yaku_list(&mut yaku_list, player.is_menzen);
+    let is_yakuman = count_yakuman(&yaku_list, rules) > 0;
+
+    let yaku_han = yaku_list
+        .iter()
+        .map(|yaku| match yakuman_multiplier(yaku) {
+            0 => (*yaku, get_han_value(yaku, player.is_menzen)),
+            n => (*yaku, 13 * n as u8),
+        })
+        .collect();
+
+    // Yakuman are not scored by fu
+    let fu_sources = if is_yakuman {
+        Vec::new()
+    } else {
+        fu_breakdown(
+            &yaku_result.hand_structure,
+            &yaku_list,
+            player,
+            game,
+            agari_type,
+            rules,
+        )
+    };
+
+    let (winning_tile, machi) = match &yaku_result.hand_structure {
+        HandStructure::YonmentsuIchiatama(hand) => (hand.agari_hai, hand.machi),
+        HandStructure::ChuurenPoutou { hand, .. } => (hand.agari_hai, hand.machi),
+        HandStructure::Chiitoitsu {
+            agari_hai, machi, ..
+        } => (*agari_hai, *machi),
+        HandStructure::KokushiMusou {
+            _agari_hai, _machi, ..
+        } => (*_agari_hai, *_machi),
+    };
+
+    Analysis {
+        is_menzen: player.is_menzen,
         agari_type,
-        is_oya: player.is_oya,
+        winning_tile,
+        machi,
+        yaku_han,
+        fu_sources,
     }
 }
 
@@ -137,10 +394,37 @@ fn calculate_han(yaku_list: &[Yaku], is_menzen: bool) -> u8 {
         .sum()
 }
 
-fn get_han_value(yaku: &Yaku, is_menzen: bool) -> u8 {
+// yaku that come from the table state or luck rather than the tiles
+fn is_situational(yaku: &Yaku) -> bool {
+    matches!(
+        yaku,
+        Yaku::Riichi
+            | Yaku::DaburuRiichi
+            | Yaku::OpenRiichi
+            | Yaku::Ippatsu
+            | Yaku::MenzenTsumo
+            | Yaku::HaiteiRaoyue
+            | Yaku::HouteiRaoyui
+            | Yaku::RinshanKaihou
+            | Yaku::Chankan
+            | Yaku::Tenhou
+            | Yaku::Chiihou
+            | Yaku::Renhou
+            | Yaku::Dora
+            | Yaku::UraDora
+            | Yaku::AkaDora
+    )
+}
+
+const fn get_han_value(yaku: &Yaku, is_menzen: bool) -> u8 {
+    if !is_menzen && is_menzen_only(yaku) {
+        return 0;
+    }
+
     match yaku {
         // 1 Han
         Yaku::Riichi => 1,
+        Yaku::OpenRiichi => 1,
         Yaku::Ippatsu => 1,
         Yaku::MenzenTsumo => 1,
         Yaku::Pinfu => 1,
@@ -163,6 +447,7 @@ fn get_han_value(yaku: &Yaku, is_menzen: bool) -> u8 {
         Yaku::Sankantsu => 2,
         Yaku::Shousangen => 2,
         Yaku::Honroutou => 2,
+        Yaku::Sanrenkou => 2,
         // Kuisagari (2 -> 1)
         Yaku::SanshokuDoujun => {
             if is_menzen {
@@ -223,105 +508,173 @@ fn get_han_value(yaku: &Yaku, is_menzen: bool) -> u8 {
     }
 }
 
+// yaku that can only be scored with a fully concealed hand
+// closed-hand-only yaku: worth 0 han if the checker ever emits one for an open hand
+const fn is_menzen_only(yaku: &Yaku) -> bool {
+    matches!(
+        yaku,
+        Yaku::MenzenTsumo
+            | Yaku::Pinfu
+            | Yaku::Iipeikou
+            | Yaku::Ryanpeikou
+            | Yaku::Chiitoitsu
+            | Yaku::Riichi
+            | Yaku::DaburuRiichi
+            | Yaku::OpenRiichi
+            | Yaku::Ippatsu
+            | Yaku::Tenhou
+            | Yaku::Chiihou
+            | Yaku::Renhou
+            | Yaku::Suuankou
+            | Yaku::SuuankouTanki
+            | Yaku::KokushiMusou
+            | Yaku::KokushiMusouJusanmen
+            | Yaku::ChuurenPoutou
+            | Yaku::JunseiChuurenPoutou
+            | Yaku::Daichisei
+    )
+}
+
 fn calculate_fu(
     hand_structure: &HandStructure,
     yaku_list: &[Yaku],
     player: &PlayerContext,
     game: &GameContext,
     agari_type: AgariType,
+    rules: &Ruleset,
 ) -> u8 {
-    // Chiitoitsu
-    if yaku_list.contains(&Yaku::Chiitoitsu) {
+    // Chiitoitsu (25 fu, not rounded up)
+    if yaku_list.contains(&Yaku::Chiitoitsu)
+        || matches!(hand_structure, HandStructure::Chiitoitsu { .. })
+    {
         return 25;
     }
 
-    // Pinfu
-    if yaku_list.contains(&Yaku::Pinfu) {
-        return if agari_type == AgariType::Tsumo {
-            20
-        } else {
-            30
-        };
+    let fu: u32 = fu_breakdown(hand_structure, yaku_list, player, game, agari_type, rules)
+        .iter()
+        .map(|(_, fu)| *fu as u32)
+        .sum();
+
+    (fu.div_ceil(10) * 10) as u8
+}
+
+// every fu source before rounding up, e.g. [("Base", 20), ("Menzen Ron", 10)]
+fn fu_breakdown(
+    hand_structure: &HandStructure,
+    yaku_list: &[Yaku],
+    player: &PlayerContext,
+    game: &GameContext,
+    agari_type: AgariType,
+    rules: &Ruleset,
+) -> Vec<(&'static str, u8)> {
+    // Chiitoitsu
+    if yaku_list.contains(&Yaku::Chiitoitsu) {
+        return vec![("Chiitoitsu", 25)];
     }
 
     // Standard Fu
-    let mut fu = 20;
+    let mut fu = vec![("Base", 20)];
 
     let hand = match hand_structure {
         HandStructure::YonmentsuIchiatama(h) => h,
         HandStructure::ChuurenPoutou { hand, .. } => hand,
-        HandStructure::Chiitoitsu { .. } => return 25,
-        HandStructure::KokushiMusou { .. } => return 0,
+        HandStructure::Chiitoitsu { .. } => return vec![("Chiitoitsu", 25)],
+        HandStructure::KokushiMusou { .. } => return Vec::new(),
     };
 
-    // Agari Type
+    // Agari Type: Tsumo +2 and Menzen Ron +10 never both apply.
+    // (Pinfu tsumo stays at 20 fu unless the ruleset keeps the tsumo fu;
+    // Pinfu ron comes to 30 fu with no extra fu below, and a kan or triplet would still be counted)
     if agari_type == AgariType::Tsumo {
-        fu += 2; // Tsumo fu
+        if !yaku_list.contains(&Yaku::Pinfu) || rules.pinfu_tsumo == PinfuTsumo::ThirtyFu {
+            fu.push(("Tsumo", 2));
+        }
     } else if player.is_menzen {
-        fu += 10; // Menzen Ron fu
+        fu.push(("Menzen Ron", 10));
     }
 
     // Melds
     for mentsu in &hand.mentsu {
-        let is_open = mentsu.is_minchou;
-        let is_yaochuu = mentsu.tiles[0].is_yaochuu();
+        // A triplet completed by Ron counts as open
+        let is_ron_triplet = agari_type == AgariType::Ron
+            && hand.machi == Machi::Shanpon
+            && mentsu.mentsu_type == MentsuType::Koutsu
+            && mentsu.tiles[0] == hand.agari_hai;
+        let is_open = mentsu.is_minchou || is_ron_triplet;
+        let is_yaochuu = mentsu.tiles[0].is_yaochuu(); // terminals and honors alike
 
         match mentsu.mentsu_type {
             MentsuType::Koutsu => {
-                fu += match (is_open, is_yaochuu) {
-                    (true, false) => 2,  // Open simple triplet
-                    (true, true) => 4,   // Open terminal/honor triplet
-                    (false, false) => 4, // Concealed simple triplet
-                    (false, true) => 8,  // Concealed terminal/honor triplet
-                };
+                fu.push(match (is_open, is_yaochuu) {
+                    (true, false) => ("Open simple triplet", 2),
+                    (true, true) => ("Open terminal/honor triplet", 4),
+                    (false, false) => ("Concealed simple triplet", 4),
+                    (false, true) => ("Concealed terminal/honor triplet", 8),
+                });
             }
             MentsuType::Kantsu => {
-                fu += match (is_open, is_yaochuu) {
-                    (true, false) => 8,   // Open simple quad
-                    (true, true) => 16,   // Open terminal/honor quad
-                    (false, false) => 16, // Concealed simple quad
-                    (false, true) => 32,  // Concealed terminal/honor quad
-                };
+                fu.push(match (is_open, is_yaochuu) {
+                    (true, false) => ("Open simple quad", 8),
+                    (true, true) => ("Open terminal/honor quad", 16),
+                    (false, false) => ("Concealed simple quad", 16),
+                    (false, true) => ("Concealed terminal/honor quad", 32),
+                });
             }
             MentsuType::Shuntsu => {} // Sequences are 0 fu
         }
     }
 
     // Pair
-    fu += get_pair_fu(&hand.atama.0, player, game);
+    let pair_fu = get_pair_fu(&hand.atama.0, player, game, rules);
+    if pair_fu > 0 {
+        fu.push(("Value pair", pair_fu as u8));
+    }
 
     // Wait
-    match hand.machi {
-        Machi::Kanchan | Machi::Penchan | Machi::Tanki => fu += 2,
-        _ => {} // Ryanmen and Shanpon are 0 fu
+    if hand.machi.fu() > 0 {
+        fu.push(("Wait", hand.machi.fu()));
+    }
+
+    // Open pinfu shape won by Ron: nothing above the base, which would leave 20 fu.
+    // It takes 2 fu so it rounds up to 30 (open Tsumo already has the 2 tsumo fu)
+    if !player.is_menzen && fu.len() == 1 {
+        fu.push(("Open pinfu", 2));
     }
 
-    (((fu + 9) / 10) * 10) as u8
+    fu
 }
 
-fn get_pair_fu(tile: &Hai, player: &PlayerContext, game: &GameContext) -> u32 {
+fn get_pair_fu(tile: &Hai, player: &PlayerContext, game: &GameContext, rules: &Ruleset) -> u32 {
     match tile {
         // Dragon Pair
         Hai::Jihai(Jihai::Sangen(_)) => 2,
         // Wind Pair
         Hai::Jihai(Jihai::Kaze(k)) => {
-            let mut fu = 0;
-            if *k == game.bakaze {
-                fu += 2; // Prevalent Wind
+            let is_bakaze = *k == game.bakaze; // Prevalent Wind
+            let is_jikaze = *k == player.jikaze; // Seat Wind
+            match (is_bakaze, is_jikaze) {
+                (true, true) => match rules.double_wind_pair_fu {
+                    DoubleWindPair::FourFu => 4,
+                    DoubleWindPair::TwoFu => 2,
+                },
+                (true, false) | (false, true) => 2,
+                (false, false) => 0,
             }
-            if *k == player.jikaze {
-                fu += 2; // Seat Wind
-            }
-            fu
         }
         _ => 0,
     }
 }
 
-fn calculate_basic_points(han: u8, fu: u8) -> (u32, Option<HandLimit>) {
+/// fu * 2^(han+2) before any limit or kiriage rounding (saturates instead of overflowing).
+/// Scoring uses the capped value; this is for explaining how a limit was reached.
+pub fn basic_points_raw(han: u8, fu: u8) -> u32 {
+    (fu as u32).saturating_mul(2u32.saturating_pow(han as u32 + 2))
+}
+
+fn calculate_basic_points(han: u8, fu: u8, rules: &Ruleset) -> (u32, Option<HandLimit>) {
     // Kazoe Yakuman
     if han >= 13 {
-        return (8000, Some(HandLimit::Yakuman));
+        return (8000, Some(HandLimit::KazoeYakuman));
     }
     // Sanbaiman
     if han >= 11 {
@@ -341,43 +694,613 @@ fn calculate_basic_points(han: u8, fu: u8) -> (u32, Option<HandLimit>) {
     }
 
     // Below Mangan
-    let basic_points = (fu as u32) * (1 << (han + 2));
+    let basic_points = basic_points_raw(han, fu);
 
-    // kiriage Mangan (cap at 2000)
+    // Mangan (cap at 2000)
     if basic_points >= 2000 {
-        (2000, Some(HandLimit::Mangan))
-    } else {
-        (basic_points, None)
+        return (2000, Some(HandLimit::Mangan));
+    }
+
+    // Kiriage Mangan (4han30fu / 3han60fu = 1920, only if enabled)
+    if rules.kiriage_mangan && basic_points == 1920 {
+        return (2000, Some(HandLimit::Mangan));
     }
+
+    (basic_points, None)
 }
 
-fn count_yakuman(yaku_list: &[Yaku]) -> u32 {
-    yaku_list
-        .iter()
-        .map(|yaku| match yaku {
-            // Double Yakuman
-            Yaku::SuuankouTanki => 2,
-            Yaku::KokushiMusouJusanmen => 2,
-            Yaku::JunseiChuurenPoutou => 2,
-            // Single Yakuman
-            Yaku::Tenhou => 1,
-            Yaku::Chiihou => 1,
-            Yaku::Renhou => 1,
-            Yaku::Daisangen => 1,
-            Yaku::Suuankou => 1,
-            Yaku::Daisuushi => 1,
-            Yaku::Shousuushi => 1,
-            Yaku::Tsuuiisou => 1,
-            Yaku::Chinroutou => 1,
-            Yaku::Ryuuiisou => 1,
-            Yaku::Suukantsu => 1,
-            Yaku::KokushiMusou => 1,
-            Yaku::ChuurenPoutou => 1,
-            _ => 0,
-        })
-        .sum()
+// total yakuman multiple, capped by max_yakuman_stack (a cap of 0 acts as 1)
+fn count_yakuman(yaku_list: &[Yaku], rules: &Ruleset) -> u32 {
+    let total: u32 = yaku_list.iter().map(yakuman_multiplier).sum();
+    match rules.max_yakuman_stack {
+        Some(cap) if total > 0 => total.min(cap.max(1) as u32),
+        _ => total,
+    }
+}
+
+const fn yakuman_multiplier(yaku: &Yaku) -> u32 {
+    match yaku {
+        // Double Yakuman
+        Yaku::SuuankouTanki => 2,
+        Yaku::KokushiMusouJusanmen => 2,
+        Yaku::JunseiChuurenPoutou => 2,
+        // Single Yakuman
+        Yaku::Tenhou => 1,
+        Yaku::Chiihou => 1,
+        Yaku::Renhou => 1,
+        Yaku::Daisangen => 1,
+        Yaku::Suuankou => 1,
+        Yaku::Daisuushi => 1,
+        Yaku::Shousuushi => 1,
+        Yaku::Tsuuiisou => 1,
+        Yaku::Chinroutou => 1,
+        Yaku::Ryuuiisou => 1,
+        Yaku::Suukantsu => 1,
+        Yaku::KokushiMusou => 1,
+        Yaku::ChuurenPoutou => 1,
+        Yaku::Suurenkou => 1,
+        Yaku::Daichisei => 1,
+        _ => 0,
+    }
+}
+
+// Canonical display order: han descending, then a fixed yaku order, dora last
+fn sort_yaku_list(yaku_list: &mut [Yaku], is_menzen: bool) {
+    yaku_list.sort_by_key(|yaku| {
+        let is_dora = matches!(yaku, Yaku::Dora | Yaku::UraDora | Yaku::AkaDora);
+        let han = match yakuman_multiplier(yaku) {
+            0 => get_han_value(yaku, is_menzen) as u32,
+            n => 13 * n,
+        };
+        (is_dora, core::cmp::Reverse(han), yaku_order(yaku))
+    });
+}
+
+// Explicit tie-break order, independent of the enum declaration order
+fn yaku_order(yaku: &Yaku) -> u8 {
+    match yaku {
+        // Yakuman
+        Yaku::Tenhou => 0,
+        Yaku::Chiihou => 1,
+        Yaku::Renhou => 2,
+        Yaku::KokushiMusouJusanmen => 3,
+        Yaku::KokushiMusou => 4,
+        Yaku::JunseiChuurenPoutou => 5,
+        Yaku::ChuurenPoutou => 6,
+        Yaku::SuuankouTanki => 7,
+        Yaku::Suuankou => 8,
+        Yaku::Daisangen => 9,
+        Yaku::Daisuushi => 10,
+        Yaku::Shousuushi => 11,
+        Yaku::Tsuuiisou => 12,
+        Yaku::Chinroutou => 13,
+        Yaku::Ryuuiisou => 14,
+        Yaku::Suukantsu => 15,
+        Yaku::Daichisei => 16,
+        Yaku::Suurenkou => 17,
+        // Situational
+        Yaku::DaburuRiichi => 20,
+        Yaku::Riichi => 21,
+        Yaku::Ippatsu => 22,
+        Yaku::MenzenTsumo => 23,
+        Yaku::HaiteiRaoyue => 24,
+        Yaku::HouteiRaoyui => 25,
+        Yaku::RinshanKaihou => 26,
+        Yaku::Chankan => 27,
+        Yaku::OpenRiichi => 28,
+        // Hand shape
+        Yaku::Pinfu => 30,
+        Yaku::Tanyao => 31,
+        Yaku::Iipeikou => 32,
+        Yaku::Ryanpeikou => 33,
+        Yaku::YakuhaiJikaze => 34,
+        Yaku::YakuhaiBakaze => 35,
+        Yaku::YakuhaiSangenpai => 36,
+        Yaku::Chiitoitsu => 37,
+        Yaku::SanshokuDoujun => 38,
+        Yaku::Ittsu => 39,
+        Yaku::Chanta => 40,
+        Yaku::Junchan => 41,
+        Yaku::Toitoi => 42,
+        Yaku::Sanankou => 43,
+        Yaku::SanshokuDoukou => 44,
+        Yaku::Sankantsu => 45,
+        Yaku::Shousangen => 46,
+        Yaku::Honroutou => 47,
+        Yaku::Honitsu => 48,
+        Yaku::Chinitsu => 49,
+        Yaku::Sanrenkou => 50,
+        // Dora
+        Yaku::Dora => 60,
+        Yaku::UraDora => 61,
+        Yaku::AkaDora => 62,
+    }
 }
 
 fn round_up_100(n: u32) -> u32 {
-    (n + 99) / 100 * 100
+    n.div_ceil(100) * 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::calculate_agari;
+    use crate::implements::calculate_agari_with_analysis;
+    use crate::implements::calculate_agari_with_rules;
+    use crate::implements::input::OpenMeldInput;
+    use crate::implements::rules::PinfuTsumo;
+    use crate::implements::test_hands::{dealer_hand, hand, tile, tiles};
+    use crate::implements::tiles::tile_to_index;
+
+    #[test]
+    fn points_from_han_fu_matches_the_table() {
+        let ko_ron = |han, fu| points_from_han_fu(han, fu, false, AgariType::Ron, 0).total_payment;
+        assert_eq!(ko_ron(3, 40), 5200);
+        assert_eq!(ko_ron(1, 30), 1000);
+        assert_eq!(ko_ron(2, 25), 1600);
+        assert_eq!(ko_ron(4, 40), 8000);
+
+        let oya_ron = points_from_han_fu(3, 40, true, AgariType::Ron, 0);
+        assert_eq!(oya_ron.total_payment, 7700);
+
+        // 1han30fu non-dealer tsumo: 300 from each non-dealer, 500 from the dealer
+        let ko_tsumo = points_from_han_fu(1, 30, false, AgariType::Tsumo, 0);
+        assert_eq!((ko_tsumo.ko_payment, ko_tsumo.oya_payment), (300, 500));
+        assert_eq!(ko_tsumo.total_payment, 1100);
+    }
+
+    #[test]
+    fn points_from_han_fu_adds_honba() {
+        let result = points_from_han_fu(3, 40, false, AgariType::Ron, 2);
+        assert_eq!(result.total_payment, 5800);
+        assert_eq!(result.honba_bonus, 600);
+    }
+
+    // one arm per variant and no wildcard: a new Yaku variant won't compile
+    // until it is numbered here, and then the test below wants it in all_yaku
+    fn variant_number(yaku: Yaku) -> usize {
+        match yaku {
+            Yaku::Riichi => 0,
+            Yaku::OpenRiichi => 1,
+            Yaku::Ippatsu => 2,
+            Yaku::MenzenTsumo => 3,
+            Yaku::Pinfu => 4,
+            Yaku::Iipeikou => 5,
+            Yaku::HaiteiRaoyue => 6,
+            Yaku::HouteiRaoyui => 7,
+            Yaku::RinshanKaihou => 8,
+            Yaku::Chankan => 9,
+            Yaku::Tanyao => 10,
+            Yaku::YakuhaiJikaze => 11,
+            Yaku::YakuhaiBakaze => 12,
+            Yaku::YakuhaiSangenpai => 13,
+            Yaku::DaburuRiichi => 14,
+            Yaku::Chiitoitsu => 15,
+            Yaku::SanshokuDoujun => 16,
+            Yaku::Ittsu => 17,
+            Yaku::Chanta => 18,
+            Yaku::Toitoi => 19,
+            Yaku::Sanankou => 20,
+            Yaku::SanshokuDoukou => 21,
+            Yaku::Sankantsu => 22,
+            Yaku::Shousangen => 23,
+            Yaku::Honroutou => 24,
+            Yaku::Sanrenkou => 25,
+            Yaku::Ryanpeikou => 26,
+            Yaku::Junchan => 27,
+            Yaku::Honitsu => 28,
+            Yaku::Chinitsu => 29,
+            Yaku::Tenhou => 30,
+            Yaku::Chiihou => 31,
+            Yaku::Renhou => 32,
+            Yaku::Daisangen => 33,
+            Yaku::Suuankou => 34,
+            Yaku::Daisuushi => 35,
+            Yaku::Shousuushi => 36,
+            Yaku::Tsuuiisou => 37,
+            Yaku::Chinroutou => 38,
+            Yaku::Ryuuiisou => 39,
+            Yaku::Suukantsu => 40,
+            Yaku::KokushiMusou => 41,
+            Yaku::ChuurenPoutou => 42,
+            Yaku::SuuankouTanki => 43,
+            Yaku::KokushiMusouJusanmen => 44,
+            Yaku::JunseiChuurenPoutou => 45,
+            Yaku::Daichisei => 46,
+            Yaku::Suurenkou => 47,
+            Yaku::Dora => 48,
+            Yaku::UraDora => 49,
+            Yaku::AkaDora => 50,
+        }
+    }
+
+    #[test]
+    fn all_yaku_lists_every_variant_once() {
+        let mut seen = [false; 51];
+        for info in all_yaku() {
+            let n = variant_number(info.yaku);
+            assert!(!seen[n], "{:?} is listed twice", info.yaku);
+            seen[n] = true;
+        }
+        assert!(seen.iter().all(|&listed| listed));
+    }
+
+    #[test]
+    fn intrinsic_han_strips_riichi_ippatsu_and_dora() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.player_context.is_riichi = true;
+        input.player_context.is_ippatsu = true;
+        input.game_context.dora_indicators = tiles("1m");
+        let result = calculate_agari(&input).unwrap();
+        assert_eq!(
+            result.yaku_list,
+            vec![
+                Yaku::Riichi,
+                Yaku::Ippatsu,
+                Yaku::Pinfu,
+                Yaku::Tanyao,
+                Yaku::Dora
+            ]
+        );
+
+        // Pinfu + Tanyao
+        assert_eq!(intrinsic_han(&result, true, &Ruleset::default()), 2);
+    }
+
+    #[test]
+    fn non_dealer_tsumo_deltas_sum_to_zero() {
+        let input = hand("234567m345678p44s", "8p", true);
+        let result = calculate_agari(&input).unwrap();
+        let deltas = tsumo_deltas(&result, Kaze::Nan).unwrap();
+
+        assert_eq!(deltas.iter().map(|(_, delta)| delta).sum::<i32>(), 0);
+        assert_eq!(deltas[0], (Kaze::Ton, -(result.oya_payment as i32)));
+        assert_eq!(deltas[2], (Kaze::Shaa, -(result.ko_payment as i32)));
+    }
+
+    #[test]
+    fn tsumo_deltas_add_honba_and_sticks() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        input.game_context.honba = 2;
+        input.game_context.riichi_bou = 1;
+        let result = calculate_agari(&input).unwrap();
+        let deltas = tsumo_deltas(&result, Kaze::Nan).unwrap();
+
+        // each payer adds 200 for two honba; the winner also takes the 1000 stick
+        assert_eq!(deltas[3], (Kaze::Pei, -(result.ko_payment as i32 + 200)));
+        assert_eq!(deltas.iter().map(|(_, delta)| delta).sum::<i32>(), 1000);
+    }
+
+    #[test]
+    fn tsumo_deltas_reject_a_dealer_result_off_east_and_a_ron() {
+        let input = dealer_hand("234567m345678p44s", "8p", true);
+        let result = calculate_agari(&input).unwrap();
+        assert_eq!(
+            tsumo_deltas(&result, Kaze::Nan),
+            Err("Invalid tsumo deltas: the dealer (is_oya) must be the East seat.")
+        );
+
+        let ron = calculate_agari(&hand("234567m345678p44s", "8p", false)).unwrap();
+        assert_eq!(
+            tsumo_deltas(&ron, Kaze::Nan),
+            Err("Invalid tsumo deltas: the result is not a Tsumo win.")
+        );
+    }
+
+    #[test]
+    fn yaku_list_puts_dora_last() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        input.player_context.is_riichi = true;
+        input.game_context.dora_indicators = tiles("3m");
+        let result = calculate_agari(&input).unwrap();
+        assert_eq!(
+            result.yaku_list,
+            vec![
+                Yaku::Riichi,
+                Yaku::MenzenTsumo,
+                Yaku::Pinfu,
+                Yaku::Tanyao,
+                Yaku::Dora
+            ]
+        );
+    }
+
+    #[test]
+    fn yaku_list_sorts_by_han_first() {
+        // Chinitsu (6) before Iipeikou (1), then the dora
+        let mut input = hand("112233345678m99m", "9m", false);
+        input.game_context.dora_indicators = tiles("8m");
+        let result = calculate_agari(&input).unwrap();
+        assert_eq!(result.yaku_list[0], Yaku::Chinitsu);
+        assert_eq!(result.yaku_list.last(), Some(&Yaku::Dora));
+    }
+
+    #[test]
+    fn double_wind_pair_fu_follows_the_ruleset() {
+        // dealer in East round: 11z is a double-wind pair.
+        // 20 base + 2 tsumo + 4 closed 222m + 2 kanchan + the pair fu (4 or 2)
+        let input = dealer_hand("222m345p678s567s11z", "4p", true);
+
+        let four = calculate_agari(&input).unwrap();
+        assert_eq!(four.fu, 40); // 32 rounds up
+
+        let rules = Ruleset {
+            double_wind_pair_fu: DoubleWindPair::TwoFu,
+            ..Ruleset::default()
+        };
+        let two = calculate_agari_with_rules(&input, &rules).unwrap();
+        assert_eq!(two.fu, 30);
+    }
+
+    #[test]
+    fn open_hand_scores_no_iipeikou() {
+        let mut input = hand("223344m678p55s", "4m", false);
+        input.open_melds = vec![OpenMeldInput::pon(tile("7z"))];
+        input.player_context.is_menzen = false;
+        let result = calculate_agari(&input).unwrap();
+
+        assert!(!result.has_yaku(Yaku::Iipeikou));
+        assert_eq!(result.yaku_list, vec![Yaku::YakuhaiSangenpai]);
+        assert_eq!(result.han, 1);
+        assert_eq!(get_han_value(&Yaku::Iipeikou, false), 0);
+    }
+
+    #[test]
+    fn four_han_forty_fu_is_a_true_mangan() {
+        let rules = Ruleset::default();
+        assert_eq!(
+            calculate_basic_points(4, 40, &rules),
+            (2000, Some(HandLimit::Mangan))
+        );
+        assert_eq!(calculate_basic_points(4, 30, &rules), (1920, None));
+    }
+
+    #[test]
+    fn kiriage_rounds_four_han_thirty_fu_up() {
+        let kiriage = Ruleset {
+            kiriage_mangan: true,
+            ..Ruleset::default()
+        };
+        assert_eq!(
+            calculate_basic_points(4, 30, &kiriage),
+            (2000, Some(HandLimit::Mangan))
+        );
+        assert_eq!(
+            calculate_basic_points(3, 60, &kiriage),
+            (2000, Some(HandLimit::Mangan))
+        );
+        assert_eq!(calculate_basic_points(3, 50, &kiriage), (1600, None));
+    }
+
+    #[test]
+    fn honba_bonus_is_split_out_at_two_honba() {
+        let mut ron = hand("234567m345678p44s", "8p", false);
+        ron.game_context.honba = 2;
+        let result = calculate_agari(&ron).unwrap();
+        assert_eq!(result.honba_bonus, 600);
+        assert_eq!(result.total_payment, 2000 + 600);
+
+        // Tsumo: 100 per honba from each of the three payers
+        let mut tsumo = hand("234567m345678p44s", "8p", true);
+        tsumo.game_context.honba = 2;
+        let result = calculate_agari(&tsumo).unwrap();
+        assert_eq!(result.honba_bonus, 600);
+        assert_eq!(result.total_payment, 2700 + 600);
+    }
+
+    #[test]
+    fn max_yakuman_stack_caps_daisangen_with_tsuuiisou() {
+        let mut input = hand("55566677722z", "2z", false);
+        input.open_melds = vec![OpenMeldInput::pon(tile("1z"))];
+        input.player_context.is_menzen = false;
+
+        let stacked = calculate_agari(&input).unwrap();
+        assert!(stacked.has_yaku(Yaku::Daisangen));
+        assert!(stacked.has_yaku(Yaku::Tsuuiisou));
+        assert_eq!(stacked.total_payment, 64000);
+
+        let capped = Ruleset {
+            max_yakuman_stack: Some(1),
+            ..Ruleset::default()
+        };
+        let result = calculate_agari_with_rules(&input, &capped).unwrap();
+        assert_eq!(result.total_payment, 32000);
+        assert_eq!(result.limit_name, Some(HandLimit::Yakuman));
+    }
+
+    #[test]
+    fn three_yakuman_pay_the_triple_limit() {
+        // Daisangen + Tsuuiisou + Suuankou (tsumo on a shanpon wait)
+        let result = calculate_agari(&hand("55566677711122z", "1z", true)).unwrap();
+        assert_eq!(
+            result.yaku_list,
+            vec![Yaku::Suuankou, Yaku::Daisangen, Yaku::Tsuuiisou]
+        );
+        assert_eq!(result.limit_name, Some(HandLimit::TripleYakuman));
+        assert_eq!(result.total_payment, 3 * 32000);
+        assert_eq!((result.oya_payment, result.ko_payment), (48000, 24000));
+    }
+
+    #[test]
+    fn score_value_sums_a_tsumo_by_hand() {
+        let mut input = hand("234567m345678p44s", "8p", true);
+        input.game_context.honba = 1;
+        input.game_context.riichi_bou = 2;
+        let result = calculate_agari(&input).unwrap();
+
+        let from_dealer = result.oya_payment + 100;
+        let from_each_other = result.ko_payment + 100;
+        let sticks = 2 * 1000;
+        let expected = from_dealer + 2 * from_each_other + sticks;
+        assert_eq!((result.oya_payment, result.ko_payment), (1300, 700));
+        assert_eq!(score_value(&result), expected);
+        assert_eq!(expected, 5000);
+
+        // the dealer is paid the same by all three
+        let mut dealer = dealer_hand("234567m345678p44s", "8p", true);
+        dealer.game_context.honba = 1;
+        let result = calculate_agari(&dealer).unwrap();
+        assert_eq!(score_value(&result), (result.oya_payment + 100) * 3);
+    }
+
+    #[test]
+    fn menzen_only_yaku_are_worth_nothing_open() {
+        // as if the checker had emitted Ryanpeikou for an open hand
+        let forced = vec![Yaku::Ryanpeikou, Yaku::Tanyao];
+        assert_eq!(count_han(&forced, true, &Ruleset::default()), 4);
+        assert_eq!(count_han(&forced, false, &Ruleset::default()), 1);
+
+        for yaku in [
+            Yaku::Ryanpeikou,
+            Yaku::Iipeikou,
+            Yaku::Chiitoitsu,
+            Yaku::Pinfu,
+            Yaku::MenzenTsumo,
+            Yaku::DaburuRiichi,
+            Yaku::Riichi,
+            Yaku::Ippatsu,
+        ] {
+            assert!(get_han_value(&yaku, true) > 0, "{yaku:?}");
+            assert_eq!(get_han_value(&yaku, false), 0, "{yaku:?}");
+        }
+    }
+
+    #[test]
+    fn pinfu_tsumo_follows_the_ruleset() {
+        let input = hand("234567m345678p44s", "8p", true);
+        let with = |pinfu_tsumo| {
+            let rules = Ruleset {
+                pinfu_tsumo,
+                ..Ruleset::default()
+            };
+            calculate_agari_with_rules(&input, &rules).unwrap()
+        };
+
+        let default = with(PinfuTsumo::TwentyFu);
+        assert!(default.has_yaku(Yaku::Pinfu));
+        assert_eq!((default.han, default.fu), (3, 20));
+        assert_eq!(default, calculate_agari(&input).unwrap());
+
+        let thirty = with(PinfuTsumo::ThirtyFu);
+        assert!(thirty.has_yaku(Yaku::Pinfu));
+        assert_eq!((thirty.han, thirty.fu), (3, 30));
+
+        let no_pinfu = with(PinfuTsumo::NoPinfu);
+        assert!(!no_pinfu.has_yaku(Yaku::Pinfu));
+        assert_eq!((no_pinfu.han, no_pinfu.fu), (2, 30));
+    }
+
+    #[test]
+    fn honitsu_dragon_triplet_adds_eight_fu() {
+        let mut input = hand("123456678m99m555z", "8m", false);
+        input.game_context.dora_indicators = tiles("9s");
+        let (result, analysis) =
+            calculate_agari_with_analysis(&input, &Ruleset::default()).unwrap();
+
+        assert!(result.has_yaku(Yaku::Honitsu));
+        assert!(
+            analysis
+                .fu_sources
+                .contains(&("Concealed terminal/honor triplet", 8))
+        );
+        // 20 base + 10 closed ron + 8 = 38, rounded up
+        assert_eq!(result.fu, 40);
+    }
+
+    #[test]
+    fn structure_is_the_scored_reading() {
+        // readable as seven pairs too; Ryanpeikou scores higher
+        let result = calculate_agari(&hand("223344m223344p55s", "5s", false)).unwrap();
+        let Some(HandStructure::YonmentsuIchiatama(agari)) = result.structure else {
+            panic!("expected the standard reading, got {:?}", result.structure);
+        };
+
+        assert_eq!(agari.atama, (tile("5s"), tile("5s")));
+        assert_eq!((agari.agari_hai, agari.machi), (tile("5s"), Machi::Tanki));
+        let mut scored: Vec<Hai> = agari
+            .mentsu
+            .iter()
+            .flat_map(|m| m.tiles[..3].to_vec())
+            .collect();
+        scored.extend([agari.atama.0, agari.atama.1]);
+        scored.sort_by_key(tile_to_index);
+        assert_eq!(scored, tiles("223344m223344p55s"));
+    }
+
+    #[test]
+    fn score_delta_of_riichi_against_riichi_and_dora() {
+        let mut riichi = hand("234567m345678p44s", "8p", false);
+        riichi.player_context.is_riichi = true;
+        let mut riichi_dora = riichi.clone();
+        riichi_dora.game_context.dora_indicators = tiles("3s");
+
+        // 3 han 30 fu (3900) against 5 han (Mangan, 8000)
+        let a = calculate_agari(&riichi_dora).unwrap();
+        let b = calculate_agari(&riichi).unwrap();
+        assert_eq!(score_delta(&a, &b), 8000 - 3900);
+        assert_eq!(score_delta(&b, &a), 3900 - 8000);
+        assert_eq!(score_delta(&a, &a), 0);
+    }
+
+    #[test]
+    fn raw_basic_points_against_capped_at_the_kiriage_boundary() {
+        let kiriage = Ruleset {
+            kiriage_mangan: true,
+            ..Ruleset::default()
+        };
+        // 4han30fu and 3han60fu are 1920 raw: rounded up to Mangan only under kiriage
+        for (han, fu) in [(4, 30), (3, 60)] {
+            assert_eq!(basic_points_raw(han, fu), 1920);
+            assert_eq!(
+                calculate_basic_points(han, fu, &Ruleset::default()),
+                (1920, None)
+            );
+            assert_eq!(
+                calculate_basic_points(han, fu, &kiriage),
+                (2000, Some(HandLimit::Mangan))
+            );
+        }
+        // 4han40fu is past the cap either way
+        assert_eq!(basic_points_raw(4, 40), 2560);
+        assert_eq!(
+            calculate_basic_points(4, 40, &Ruleset::default()),
+            (2000, Some(HandLimit::Mangan))
+        );
+    }
+
+    #[test]
+    fn menzen_ron_and_tsumo_fu_are_exclusive() {
+        // 68p waits on 7p (kanchan, 2 fu); tanyao is the yaku
+        let closed_ron = calculate_agari(&hand("234567m345678p44s", "7p", false)).unwrap();
+        assert_eq!(closed_ron.fu, 40); // 20 + 10 menzen ron + 2 = 32
+        let closed_tsumo = calculate_agari(&hand("234567m345678p44s", "7p", true)).unwrap();
+        assert_eq!(closed_tsumo.fu, 30); // 20 + 2 tsumo + 2 = 24
+
+        let open = |all: &str, win: &str, tsumo: bool| {
+            let mut input = hand(all, win, tsumo);
+            input.open_melds = vec![OpenMeldInput::chi(tile("2m"))];
+            input.player_context.is_menzen = false;
+            calculate_agari(&input).unwrap()
+        };
+        // no menzen ron fu once open
+        assert_eq!(open("567m345678p44s", "7p", false).fu, 30); // kanchan: 20 + 2 = 22
+        // open pinfu shape won by ron: floor of 30
+        assert_eq!(open("567m345678p44s", "8p", false).fu, 30);
+        assert_eq!(open("567m345678p44s", "8p", true).fu, 30); // 20 + 2 tsumo
+    }
+
+    #[test]
+    fn the_higher_fu_placement_of_the_winning_tile_is_scored() {
+        // 7s completes 56s (ryanmen, 30 fu) or 68s (kanchan, 40 fu)
+        let mut input = hand("222m44p566778s", "7s", true);
+        input.open_melds = vec![OpenMeldInput::pon(tile("7z"))];
+        input.player_context.is_menzen = false;
+        let result = calculate_agari(&input).unwrap();
+
+        assert_eq!(result.fu, 40); // 20 + 2 tsumo + 4 closed 222m + 4 open chun + 2 kanchan = 32
+        let Some(HandStructure::YonmentsuIchiatama(agari)) = &result.structure else {
+            panic!("a standard hand");
+        };
+        assert_eq!(agari.machi, Machi::Kanchan);
+    }
 }
@@ -0,0 +1,320 @@
+// shanten_calculator.rs: Shanten, waits and riichi eligibility of a concealed hand
+
+use super::types::tiles::{Hai, index_to_tile, tile_to_index};
+use alloc::vec::Vec;
+
+/// Shanten number of the concealed tiles (-1 = complete, 0 = tenpai).
+/// Declared melds are inferred from the tile count (13/14 = none, 10/11 = one, ...).
+/// Counts that aren't a hand (a tile more than 4 times, more than 14 tiles) give i8::MAX.
+pub fn shanten(counts: &[u8; 34]) -> i8 {
+    let Some(total) = hand_size(counts) else {
+        return i8::MAX;
+    };
+    let mut best = standard_shanten(counts, total);
+
+    // Chiitoitsu and Kokushi need all 13/14 tiles concealed
+    if total >= 13 {
+        best = best.min(chiitoitsu_shanten(counts));
+        best = best.min(kokushi_shanten(counts));
+    }
+    best
+}
+
+/// Tiles that complete a tenpai hand (excluding tiles already held 4 times)
+pub fn waits(counts: &[u8; 34]) -> Vec<Hai> {
+    let mut temp = *counts;
+    let mut result = Vec::new();
+
+    // only a pre-win hand (13, 10, 7, ... tiles) can wait
+    if hand_size(counts).is_none_or(|total| total % 3 != 1) {
+        return result;
+    }
+
+    for i in 0..34 {
+        if temp[i] >= 4 {
+            continue;
+        }
+        temp[i] += 1;
+        if shanten(&temp) == -1 {
+            result.push(index_to_tile(i));
+        }
+        temp[i] -= 1;
+    }
+    result
+}
+
+/// Tiles that lower the shanten number, with the copies not already in the hand
+pub fn ukeire(hand: &[u8; 34]) -> Vec<(Hai, u8)> {
+    ukeire_with_visible(hand, &[])
+}
+
+/// ukeire, also discounting tiles seen elsewhere (dora indicators, melds, discards).
+/// A tile with no copies left is still listed, with a count of 0.
+pub fn ukeire_with_visible(hand: &[u8; 34], visible: &[Hai]) -> Vec<(Hai, u8)> {
+    let mut result = Vec::new();
+
+    // only a hand waiting for a draw (13, 10, 7, ... tiles) has ukeire
+    if hand_size(hand).is_none_or(|total| total % 3 != 1) {
+        return result;
+    }
+
+    let mut seen = *hand;
+    for tile in visible {
+        seen[tile_to_index(tile)] = seen[tile_to_index(tile)].saturating_add(1);
+    }
+
+    let current = shanten(hand);
+    let mut temp = *hand;
+    for i in 0..34 {
+        if temp[i] >= 4 {
+            continue;
+        }
+        temp[i] += 1;
+        if shanten(&temp) < current {
+            result.push((index_to_tile(i), 4u8.saturating_sub(seen[i])));
+        }
+        temp[i] -= 1;
+    }
+    result
+}
+
+/// Riichi needs a closed hand that is tenpai before the winning tile.
+/// counts holds the concealed tiles only; closed kans count as declared melds.
+pub fn can_declare_riichi(counts: &[u8; 34], is_menzen: bool) -> bool {
+    is_menzen && shanten(counts) == 0
+}
+
+/// can_declare_riichi where counts still includes the 4 tiles of each closed kan.
+/// The kans are taken out first, as organize_hand does; false if a kan's tiles are missing.
+pub fn can_declare_riichi_with_kans(
+    counts: &[u8; 34],
+    closed_kans: &[Hai],
+    is_menzen: bool,
+) -> bool {
+    let mut concealed = *counts;
+    for kan_tile in closed_kans {
+        let count = &mut concealed[tile_to_index(kan_tile)];
+        if *count < 4 {
+            return false;
+        }
+        *count -= 4;
+    }
+    can_declare_riichi(&concealed, is_menzen)
+}
+
+/// Likely reason the tiles do not make a complete hand, for error messages
+pub fn diagnose(counts: &[u8; 34]) -> &'static str {
+    let Some(total) = hand_size(counts) else {
+        return "Invalid hand: more than 4 of a tile or more than 14 tiles.";
+    };
+    if total % 3 != 2 {
+        return "Invalid hand: wrong number of tiles for a complete hand.";
+    }
+    if !counts.iter().any(|&c| c >= 2) {
+        return "Invalid hand: no tile can form the pair.";
+    }
+    match shanten(counts) {
+        -1 => "Complete hand: no problem found.",
+        0 => "Invalid hand: cannot form four melds and a pair (one tile off).",
+        _ => "Invalid hand: not one tile away from a complete hand.",
+    }
+}
+
+// ---Helper Functions---
+
+// number of tiles, or None if the counts can't be a hand (a tile held more
+// than 4 times or more than 14 tiles); also keeps the sums below from overflowing
+pub(crate) fn hand_size(counts: &[u8; 34]) -> Option<usize> {
+    let total: usize = counts.iter().map(|&c| c as usize).sum();
+    (total <= 14 && counts.iter().all(|&c| c <= 4)).then_some(total)
+}
+
+// counts already checked by hand_size
+fn standard_shanten(counts: &[u8; 34], total: usize) -> i8 {
+    let called_melds = (14usize.saturating_sub(total) / 3) as i8;
+
+    let mut temp = *counts;
+    let mut best = 8;
+    scan_groups(&mut temp, 0, called_melds, 0, 0, &mut best);
+    best
+}
+
+// 8 - 2 * melds - partial melds - pair, with melds + partial melds capped at 4
+fn scan_groups(
+    counts: &mut [u8; 34],
+    start: usize,
+    mentsu: i8,
+    taatsu: i8,
+    atama: i8,
+    best: &mut i8,
+) {
+    let mut i = start;
+    while i < 34 && counts[i] == 0 {
+        i += 1;
+    }
+    if i == 34 {
+        let taatsu = taatsu.min(4 - mentsu);
+        *best = (*best).min(8 - 2 * mentsu - taatsu - atama);
+        return;
+    }
+
+    let is_suhai = i < 27;
+
+    // Koutsu
+    if counts[i] >= 3 {
+        counts[i] -= 3;
+        scan_groups(counts, i, mentsu + 1, taatsu, atama, best);
+        counts[i] += 3;
+    }
+
+    // Shuntsu
+    if is_suhai && i % 9 < 7 && counts[i + 1] > 0 && counts[i + 2] > 0 {
+        counts[i] -= 1;
+        counts[i + 1] -= 1;
+        counts[i + 2] -= 1;
+        scan_groups(counts, i, mentsu + 1, taatsu, atama, best);
+        counts[i] += 1;
+        counts[i + 1] += 1;
+        counts[i + 2] += 1;
+    }
+
+    // Atama
+    if atama == 0 && counts[i] >= 2 {
+        counts[i] -= 2;
+        scan_groups(counts, i, mentsu, taatsu, 1, best);
+        counts[i] += 2;
+    }
+
+    // Taatsu (pair, two-sided/edge, closed)
+    if counts[i] >= 2 {
+        counts[i] -= 2;
+        scan_groups(counts, i, mentsu, taatsu + 1, atama, best);
+        counts[i] += 2;
+    }
+    for gap in 1..=2 {
+        if is_suhai && i % 9 + gap < 9 && counts[i + gap] > 0 {
+            counts[i] -= 1;
+            counts[i + gap] -= 1;
+            scan_groups(counts, i, mentsu, taatsu + 1, atama, best);
+            counts[i] += 1;
+            counts[i + gap] += 1;
+        }
+    }
+
+    // Isolated tile
+    counts[i] -= 1;
+    scan_groups(counts, i, mentsu, taatsu, atama, best);
+    counts[i] += 1;
+}
+
+fn chiitoitsu_shanten(counts: &[u8; 34]) -> i8 {
+    let pairs = counts.iter().filter(|&&c| c >= 2).count() as i8;
+    let kinds = counts.iter().filter(|&&c| c >= 1).count() as i8;
+    6 - pairs + (7 - kinds).max(0)
+}
+
+fn kokushi_shanten(counts: &[u8; 34]) -> i8 {
+    let yaochuu = (0..34).filter(|&i| index_to_tile(i).is_yaochuu());
+    let kinds = yaochuu.clone().filter(|&i| counts[i] >= 1).count() as i8;
+    let has_pair = yaochuu.clone().any(|i| counts[i] >= 2);
+    13 - kinds - has_pair as i8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::test_hands::{counts, tile, tiles};
+    use alloc::vec;
+
+    #[test]
+    fn riichi_needs_tenpai() {
+        // 23m waits on 1m/4m
+        let tenpai = counts("23m456789p123s99s");
+        assert!(can_declare_riichi(&tenpai, true));
+        assert!(!can_declare_riichi(&tenpai, false));
+
+        let one_shanten = counts("29m456789p123s99s");
+        assert_eq!(shanten(&one_shanten), 1);
+        assert!(!can_declare_riichi(&one_shanten, true));
+    }
+
+    #[test]
+    fn impossible_counts_do_not_overflow() {
+        let mut bad = [0u8; 34];
+        bad[0] = 200;
+        bad[1] = 100;
+        assert_eq!(shanten(&bad), i8::MAX);
+        assert!(waits(&bad).is_empty());
+        assert!(ukeire(&bad).is_empty());
+        assert!(!can_declare_riichi(&bad, true));
+        assert_eq!(
+            diagnose(&bad),
+            "Invalid hand: more than 4 of a tile or more than 14 tiles."
+        );
+
+        // a fifth 2m in an otherwise tenpai hand
+        let mut five_copies = counts("22223m456789p789s");
+        five_copies[1] += 1;
+        assert_eq!(shanten(&five_copies), i8::MAX);
+    }
+
+    #[test]
+    fn diagnose_names_the_likely_cause() {
+        assert_eq!(
+            diagnose(&counts("123456789m1357p9s")),
+            "Invalid hand: no tile can form the pair."
+        );
+        // three melds, a pair, and 12s + 4z where the fourth meld should be
+        assert_eq!(
+            diagnose(&counts("123456m789p12s114z")),
+            "Invalid hand: cannot form four melds and a pair (one tile off)."
+        );
+        assert_eq!(
+            diagnose(&counts("1147m258p369s1234z")),
+            "Invalid hand: not one tile away from a complete hand."
+        );
+        assert_eq!(
+            diagnose(&counts("234567m345678p44s")),
+            "Complete hand: no problem found."
+        );
+    }
+
+    #[test]
+    fn visible_copies_reduce_the_wait() {
+        // tanki on 1z, one copy in hand
+        let hand = counts("234567m345678p1z");
+        assert_eq!(ukeire_with_visible(&hand, &[]), vec![(tile("1z"), 3)]);
+        // two more copies seen elsewhere: three of the four are accounted for
+        assert_eq!(
+            ukeire_with_visible(&hand, &tiles("11z")),
+            vec![(tile("1z"), 1)]
+        );
+    }
+
+    #[test]
+    fn riichi_with_a_closed_kan() {
+        let kan = [tile("1m")];
+        // 1111m is the ankan; 234p567s789s5p waits on 5p
+        assert!(can_declare_riichi_with_kans(
+            &counts("1111m234p567s789s5p"),
+            &kan,
+            true
+        ));
+        assert!(!can_declare_riichi_with_kans(
+            &counts("1111m234p567s79s5p1z"),
+            &kan,
+            true
+        ));
+        // the kan's tiles have to be in counts
+        assert!(!can_declare_riichi_with_kans(
+            &counts("111m234p567s789s5p"),
+            &kan,
+            true
+        ));
+        assert!(!can_declare_riichi_with_kans(
+            &counts("1111m234p567s789s5p"),
+            &kan,
+            false
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+// test_hands.rs: Hand builders shared by the unit tests
+
+use super::game::{AgariType, GameContext, PlayerContext};
+use super::input::UserInput;
+use super::notation::{parse_tile, parse_tiles};
+use super::tiles::{Hai, Kaze, tile_to_index};
+use alloc::vec::Vec;
+
+/// Notation to tiles, panicking on a typo
+pub fn tiles(notation: &str) -> Vec<Hai> {
+    parse_tiles(notation).unwrap()
+}
+
+/// Exactly one tile from notation
+pub fn tile(notation: &str) -> Hai {
+    parse_tile(notation).unwrap()
+}
+
+/// A closed non-dealer (South seat) hand in East 1 with a 7z dora indicator.
+/// `all` is every concealed tile including the winning tile; on Ron it is taken out of hand_tiles.
+pub fn hand(all: &str, win: &str, tsumo: bool) -> UserInput {
+    let mut hand_tiles = tiles(all);
+    let winning_tile = tile(win);
+    if !tsumo {
+        let pos = hand_tiles.iter().position(|t| *t == winning_tile).unwrap();
+        hand_tiles.remove(pos);
+    }
+
+    UserInput {
+        hand_tiles,
+        winning_tile,
+        open_melds: Vec::new(),
+        closed_kans: Vec::new(),
+        player_context: PlayerContext {
+            jikaze: Kaze::Nan,
+            is_oya: false,
+            is_riichi: false,
+            is_daburu_riichi: false,
+            is_open_riichi: false,
+            is_ippatsu: false,
+            is_menzen: true,
+        },
+        game_context: GameContext::with_dora(tiles("7z")),
+        agari_type: if tsumo {
+            AgariType::Tsumo
+        } else {
+            AgariType::Ron
+        },
+    }
+}
+
+/// The same hand won by the dealer (East seat)
+pub fn dealer_hand(all: &str, win: &str, tsumo: bool) -> UserInput {
+    let mut input = hand(all, win, tsumo);
+    input.player_context.jikaze = Kaze::Ton;
+    input.player_context.is_oya = true;
+    input
+}
+
+/// Tile counts, indexed by tile_to_index, from notation
+pub fn counts(notation: &str) -> [u8; 34] {
+    let mut counts = [0u8; 34];
+    for t in tiles(notation) {
+        counts[tile_to_index(&t)] += 1;
+    }
+    counts
+}
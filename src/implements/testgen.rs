@@ -0,0 +1,130 @@
+// testgen.rs: Random legal winning hands for property tests (test-util feature)
+
+use super::types::{
+    game::{AgariType, GameContext, PlayerContext, SpecialWin},
+    input::UserInput,
+    tiles::{Kaze, index_to_tile},
+};
+use rand::Rng;
+
+/// Builds a complete, closed hand of four melds and a pair drawn from a
+/// simulated 136-tile wall, so no tile ever appears more than four times.
+pub fn random_winning_hand(rng: &mut impl Rng) -> UserInput {
+    let mut wall = [4u8; 34];
+    let mut hand_tiles = Vec::with_capacity(14);
+
+    // 4 Melds
+    let mut melds = 0;
+    while melds < 4 {
+        if rng.random_bool(0.5) {
+            // Shuntsu
+            let suit = rng.random_range(0..3);
+            let start = suit * 9 + rng.random_range(0..7);
+            if (start..start + 3).all(|i| wall[i] > 0) {
+                for (offset, count) in wall[start..start + 3].iter_mut().enumerate() {
+                    *count -= 1;
+                    hand_tiles.push(index_to_tile(start + offset));
+                }
+                melds += 1;
+            }
+        } else {
+            // Koutsu
+            let i = rng.random_range(0..34);
+            if wall[i] >= 3 {
+                wall[i] -= 3;
+                hand_tiles.extend([index_to_tile(i); 3]);
+                melds += 1;
+            }
+        }
+    }
+
+    // Pair
+    loop {
+        let i = rng.random_range(0..34);
+        if wall[i] >= 2 {
+            wall[i] -= 2;
+            hand_tiles.extend([index_to_tile(i); 2]);
+            break;
+        }
+    }
+
+    hand_tiles.sort();
+    let winning_tile = hand_tiles[rng.random_range(0..hand_tiles.len())];
+
+    // If Ron, the winning tile is not in hand_tiles
+    let agari_type = if rng.random_bool(0.5) {
+        AgariType::Tsumo
+    } else {
+        let pos = hand_tiles.iter().position(|t| *t == winning_tile).unwrap();
+        hand_tiles.remove(pos);
+        AgariType::Ron
+    };
+
+    // Dora indicator from the rest of the wall
+    let dora_indicator = loop {
+        let i = rng.random_range(0..34);
+        if wall[i] > 0 {
+            break index_to_tile(i);
+        }
+    };
+
+    let jikaze = Kaze::all()[rng.random_range(0..4)];
+    // Closed Tsumo always has MenzenTsumo; riichi guarantees a yaku on Ron
+    let is_riichi = agari_type == AgariType::Ron || rng.random_bool(0.5);
+
+    UserInput {
+        hand_tiles,
+        winning_tile,
+        open_melds: Vec::new(),
+        closed_kans: Vec::new(),
+        player_context: PlayerContext {
+            jikaze,
+            is_oya: jikaze == Kaze::Ton,
+            is_riichi,
+            is_daburu_riichi: false,
+            is_open_riichi: false,
+            is_ippatsu: is_riichi && rng.random_bool(0.2),
+            is_menzen: true,
+        },
+        game_context: GameContext {
+            bakaze: Kaze::all()[rng.random_range(0..2)],
+            kyoku: rng.random_range(1..=4),
+            honba: rng.random_range(0..3),
+            riichi_bou: rng.random_range(0..3),
+            dora_indicators: vec![dora_indicator],
+            uradora_indicators: Vec::new(),
+            num_akadora: 0,
+            special_win: SpecialWin::None,
+        },
+        agari_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implements::input_validator::all_tiles;
+    use crate::implements::tiles::tile_to_index;
+    use crate::implements::{calculate_agari, organize_hand};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random_hands_are_legal_and_never_panic() {
+        let mut rng = StdRng::seed_from_u64(128);
+        for _ in 0..300 {
+            let input = random_winning_hand(&mut rng);
+
+            let mut counts = [0u8; 34];
+            for tile in all_tiles(&input) {
+                counts[tile_to_index(&tile)] += 1;
+            }
+            assert_eq!(counts.iter().map(|&c| c as usize).sum::<usize>(), 14);
+            assert!(counts.iter().all(|&c| c <= 4));
+
+            assert!(organize_hand(&input).is_ok(), "{:?}", input.hand_tiles);
+            // a hand without yaku is an error, never a panic
+            let _ = calculate_agari(&input);
+        }
+    }
+}
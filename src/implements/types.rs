@@ -1,6 +1,8 @@
 // types.rs: basic types and structs
 
 pub mod tiles {
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
     pub enum Suhai {
         // 数牌 (Numbered Tiles)
@@ -9,6 +11,8 @@ pub mod tiles {
         Souzu, // 索子 (Bamboo)
     }
 
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
     pub enum Kaze {
         // 風牌 (Wind Tiles)
@@ -18,6 +22,8 @@ pub mod tiles {
         Pei,  // 北 (North)
     }
 
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
     pub enum Sangenpai {
         // 三元牌 (Dragon Tiles)
@@ -26,6 +32,8 @@ pub mod tiles {
         Chun,  // 中 (Red)
     }
 
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
     pub enum Jihai {
         // 字牌 (Honor Tiles)
@@ -33,7 +41,40 @@ pub mod tiles {
         Sangen(Sangenpai),
     }
 
+    impl Kaze {
+        // East, South, West, North
+        pub const fn all() -> [Kaze; 4] {
+            [Kaze::Ton, Kaze::Nan, Kaze::Shaa, Kaze::Pei]
+        }
+    }
+
+    impl Sangenpai {
+        // White, Green, Red
+        pub const fn all() -> [Sangenpai; 3] {
+            [Sangenpai::Haku, Sangenpai::Hatsu, Sangenpai::Chun]
+        }
+    }
+
+    impl Jihai {
+        // the four winds then the three dragons (tile index order)
+        pub const fn all() -> [Jihai; 7] {
+            [
+                Jihai::Kaze(Kaze::Ton),
+                Jihai::Kaze(Kaze::Nan),
+                Jihai::Kaze(Kaze::Shaa),
+                Jihai::Kaze(Kaze::Pei),
+                Jihai::Sangen(Sangenpai::Haku),
+                Jihai::Sangen(Sangenpai::Hatsu),
+                Jihai::Sangen(Sangenpai::Chun),
+            ]
+        }
+    }
+
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    // Arbitrary (fuzzing) draws the Suhai number from any u8, so it also
+    // produces malformed tiles such as Suhai(0, _) for the validators to reject
     pub enum Hai {
         // 牌 (Tile)
         Suhai(u8, Suhai), // 数牌 (Numbered tile, 1-9)
@@ -66,6 +107,27 @@ pub mod tiles {
         pub fn is_yaochuu(&self) -> bool {
             self.is_terminal() || self.is_jihai()
         }
+
+        // glyph from the Unicode Mahjong Tiles block (U+1F000), e.g. 1m is 🀇 and East is 🀀.
+        // The block has no red fives; a malformed tile gives U+FFFD
+        pub fn to_unicode(&self) -> char {
+            let code = match self {
+                Hai::Suhai(n, suit) if (1..=9).contains(n) => {
+                    let first = match suit {
+                        Suhai::Manzu => 0x1F007,
+                        Suhai::Souzu => 0x1F010,
+                        Suhai::Pinzu => 0x1F019,
+                    };
+                    first + (*n as u32 - 1)
+                }
+                Hai::Suhai(..) => 0xFFFD,
+                Hai::Jihai(Jihai::Kaze(kaze)) => 0x1F000 + *kaze as u32,
+                Hai::Jihai(Jihai::Sangen(Sangenpai::Chun)) => 0x1F004,
+                Hai::Jihai(Jihai::Sangen(Sangenpai::Hatsu)) => 0x1F005,
+                Hai::Jihai(Jihai::Sangen(Sangenpai::Haku)) => 0x1F006,
+            };
+            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+        }
     }
 
     pub fn tile_to_index(tile: &Hai) -> usize {
@@ -83,6 +145,14 @@ pub mod tiles {
         }
     }
 
+    // tile_to_index for tiles that may be malformed (a Suhai outside 1-9)
+    pub fn try_tile_to_index(tile: &Hai) -> Option<usize> {
+        match tile {
+            Hai::Suhai(n, _) if !(1..=9).contains(n) => None,
+            _ => Some(tile_to_index(tile)),
+        }
+    }
+
     pub fn index_to_tile(index: usize) -> Hai {
         match index {
             0..=8 => Hai::Suhai((index + 1) as u8, Suhai::Manzu),
@@ -98,12 +168,53 @@ pub mod tiles {
             _ => panic!("Invalid tile index: {}", index),
         }
     }
+
+    // membership masks over the 34-index space
+    const fn mask_of(indices: &[usize]) -> [bool; 34] {
+        let mut mask = [false; 34];
+        let mut i = 0;
+        while i < indices.len() {
+            mask[indices[i]] = true;
+            i += 1;
+        }
+        mask
+    }
+
+    // 1m 9m 1p 9p 1s 9s
+    pub const TERMINAL_MASK: [bool; 34] = mask_of(&[0, 8, 9, 17, 18, 26]);
+    // winds and dragons
+    pub const HONOR_MASK: [bool; 34] = mask_of(&[27, 28, 29, 30, 31, 32, 33]);
+    // yaochuu-hai
+    pub const TERMINAL_HONOR_MASK: [bool; 34] =
+        mask_of(&[0, 8, 9, 17, 18, 26, 27, 28, 29, 30, 31, 32, 33]);
+    // 2s 3s 4s 6s 8s and Hatsu (Ryuuiisou tiles)
+    pub const GREEN_MASK: [bool; 34] = mask_of(&[19, 20, 21, 23, 25, 32]);
+
+    // every tile present in counts is inside the mask
+    pub fn counts_only_in(counts: &[u8; 34], mask: &[bool; 34]) -> bool {
+        counts.iter().zip(mask).all(|(&c, &m)| c == 0 || m)
+    }
+
+    // order-independent hash of a tile multiset: FNV-1a over the 34 counts,
+    // so it is the same on every run and platform (unlike RandomState)
+    pub fn hand_fingerprint(tiles: &[Hai]) -> u64 {
+        let mut counts = [0u8; 34];
+        for tile in tiles {
+            let count = &mut counts[tile_to_index(tile)];
+            *count = count.saturating_add(1);
+        }
+        counts.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &count| {
+            (hash ^ count as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
 }
 
 pub mod hand {
-    use super::tiles::Hai;
+    use super::tiles::{Hai, Kaze};
+    use core::fmt;
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub enum MentsuType {
         Shuntsu, // 順子 (Sequence)
         Koutsu,  // 刻子 (Triplet)
@@ -116,8 +227,10 @@ pub mod hand {
         pub mentsu_type: MentsuType,
         pub is_minchou: bool, // 明張 (meld open?)
         pub tiles: [Hai; 4],
+        pub called_from: Option<Kaze>, // seat the meld was called from (open melds only)
     }
 
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum Machi {
         // 待ち (Wait)
@@ -132,7 +245,39 @@ pub mod hand {
         KokushiJusanmen, // 国士十三面 (Kokushi 13-sided wait)
     }
 
-    #[derive(Debug, Clone, Copy)]
+    impl Machi {
+        // fu for the wait: 2 for a single-tile wait, otherwise 0
+        pub fn fu(&self) -> u8 {
+            match self {
+                Machi::Kanchan | Machi::Penchan | Machi::Tanki => 2,
+                _ => 0, // Ryanmen and Shanpon are 0 fu (Kokushi has no fu)
+            }
+        }
+    }
+
+    impl fmt::Display for Machi {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                Machi::Ryanmen => "two-sided",
+                Machi::Tanki => "pair",
+                Machi::Penchan => "edge",
+                Machi::Kanchan => "closed",
+                Machi::Shanpon => "dual-pon",
+                Machi::KokushiIchimen => "thirteen orphans single",
+                Machi::KokushiJusanmen => "thirteen orphans 13-sided",
+            };
+            write!(f, "{}", name)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    // group of an AgariHand completed by the winning tile
+    pub enum WinGroup {
+        Pair,        // 頭 (The pair)
+        Meld(usize), // index into AgariHand.mentsu
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct AgariHand {
         // 和了手 (Winning Hand)
         pub mentsu: [Mentsu; 4], // 面子 (The 4 melds)
@@ -141,6 +286,23 @@ pub mod hand {
         pub machi: Machi,        // 待ち (The wait type)
     }
 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    // one group of an AgariHand, for drawing or walking the hand group by group
+    pub enum HandGroup<'a> {
+        Meld(&'a Mentsu), // 面子 (One of the 4 melds)
+        Pair(Hai),        // 頭 (The pair)
+    }
+
+    impl AgariHand {
+        // the 4 melds in order, then the pair
+        pub fn groups(&self) -> impl Iterator<Item = HandGroup<'_>> {
+            self.mentsu
+                .iter()
+                .map(HandGroup::Meld)
+                .chain(core::iter::once(HandGroup::Pair(self.atama.0)))
+        }
+    }
+
     #[derive(Debug, Clone)]
     pub enum HandOrganization {
         YonmentsuIchiatama(AgariHand), // 四面子一頭 (Standard Hand: 4 Melds, 1 Pair)
@@ -151,7 +313,7 @@ pub mod hand {
         },
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     pub enum HandStructure {
         YonmentsuIchiatama(AgariHand),
 
@@ -180,26 +342,99 @@ pub mod hand {
 
 pub mod game {
     use super::tiles::{Hai, Kaze};
+    use alloc::vec::Vec;
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     // the way the hand was won
     pub enum AgariType {
         Tsumo, // 自摸 (Self-draw)
         Ron,   // 栄和 (Win off discard)
     }
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     // Context for winning the hand
     pub struct PlayerContext {
         pub jikaze: Kaze,           // 自風 (Seat Wind)
         pub is_oya: bool,           // 親 (Is player the dealer?)
         pub is_riichi: bool,        // 立直 (Was Riichi declared?)
         pub is_daburu_riichi: bool, // ダブル立直 (Double Riichi)
+        pub is_open_riichi: bool,   // オープン立直 (Riichi with the hand shown, local rule)
         pub is_ippatsu: bool,       // 一発 (Ippatsu)
         pub is_menzen: bool,        // 門前 (Is the hand fully concealed?)
     }
 
-    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    // the special win condition of a hand; at most one applies, so combinations
+    // like Tenhou with Houtei can't be expressed
+    pub enum SpecialWin {
+        #[default]
+        None,
+        Tenhou,  // 天和 (Blessing of Heaven)
+        Chiihou, // 地和 (Blessing of Earth)
+        Renhou,  // 人和 (Blessing of Man)
+        Haitei,  // 海底 (Under the Sea - last draw)
+        Houtei,  // 河底 (Under the River - last discard)
+        Rinshan, // 嶺上 (After a Kan)
+        Chankan, // 搶槓 (Robbing a Kan)
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    // the seven flags GameContext held before SpecialWin, for migrating old
+    // inputs or for UIs with one checkbox per condition
+    pub struct SpecialWinFlags {
+        pub is_tenhou: bool,
+        pub is_chiihou: bool,
+        pub is_renhou: bool,
+        pub is_haitei: bool,
+        pub is_houtei: bool,
+        pub is_rinshan: bool,
+        pub is_chankan: bool,
+    }
+
+    // the one flag that is set, or an error if several are
+    impl TryFrom<SpecialWinFlags> for SpecialWin {
+        type Error = &'static str;
+
+        fn try_from(flags: SpecialWinFlags) -> Result<Self, Self::Error> {
+            let set = [
+                (flags.is_tenhou, SpecialWin::Tenhou),
+                (flags.is_chiihou, SpecialWin::Chiihou),
+                (flags.is_renhou, SpecialWin::Renhou),
+                (flags.is_haitei, SpecialWin::Haitei),
+                (flags.is_houtei, SpecialWin::Houtei),
+                (flags.is_rinshan, SpecialWin::Rinshan),
+                (flags.is_chankan, SpecialWin::Chankan),
+            ];
+            let mut set = set.iter().filter(|(is_set, _)| *is_set);
+            match (set.next(), set.next()) {
+                (None, _) => Ok(SpecialWin::None),
+                (Some((_, win)), None) => Ok(*win),
+                _ => Err("Invalid game state: at most one special win condition can apply."),
+            }
+        }
+    }
+
+    // exactly the flag for win is set
+    impl From<SpecialWin> for SpecialWinFlags {
+        fn from(win: SpecialWin) -> Self {
+            Self {
+                is_tenhou: win == SpecialWin::Tenhou,
+                is_chiihou: win == SpecialWin::Chiihou,
+                is_renhou: win == SpecialWin::Renhou,
+                is_haitei: win == SpecialWin::Haitei,
+                is_houtei: win == SpecialWin::Houtei,
+                is_rinshan: win == SpecialWin::Rinshan,
+                is_chankan: win == SpecialWin::Chankan,
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     // Context for the current round
     pub struct GameContext {
         pub bakaze: Kaze, // 場風 (Prevalent Wind)
@@ -211,24 +446,164 @@ pub mod game {
         pub dora_indicators: Vec<Hai>, // ドラ表示牌 (Dora indicators)
         pub uradora_indicators: Vec<Hai>, // 裏ドラ表示牌 (Ura Dora indicators)
         pub num_akadora: u8, // 赤ドラ (Red Dora)
-        // Special win condition flags
-        pub is_tenhou: bool,  // 天和 (Blessing of Heaven)
-        pub is_chiihou: bool, // 地和 (Blessing of Earth)
-        pub is_renhou: bool,  // 人和 (Blessing of Man)
-        pub is_haitei: bool,  // 海底 (Under the Sea - last draw)
-        pub is_houtei: bool,  // 河底 (Under the River - last discard)
-        pub is_rinshan: bool, // 嶺上 (After a Kan)
-        pub is_chankan: bool, // 搶槓 (Robbing a Kan)
+        pub special_win: SpecialWin, // Special win condition (at most one applies)
+    }
+
+    impl GameContext {
+        /// East 1 with one dora indicator: no honba or riichi sticks, no ura dora,
+        /// no red fives and no special win flags. Change the rest with struct-update syntax:
+        ///
+        /// ```
+        /// use riichi_mahjong_scoring_calculator::implements::game::GameContext;
+        /// use riichi_mahjong_scoring_calculator::implements::parse_tile;
+        /// use riichi_mahjong_scoring_calculator::implements::tiles::Kaze;
+        ///
+        /// let game = GameContext {
+        ///     bakaze: Kaze::Nan,
+        ///     honba: 2,
+        ///     ..GameContext::east_one(parse_tile("3p").unwrap())
+        /// };
+        /// assert_eq!(game.kyoku, 1);
+        /// assert_eq!(game.dora_indicators, vec![parse_tile("3p").unwrap()]);
+        /// ```
+        pub fn east_one(dora_indicator: Hai) -> Self {
+            Self::with_dora(alloc::vec![dora_indicator])
+        }
+
+        // East 1 with the given dora indicators (kan dora included)
+        pub fn with_dora(indicators: Vec<Hai>) -> Self {
+            Self {
+                bakaze: Kaze::Ton,
+                kyoku: 1,
+                honba: 0,
+                riichi_bou: 0,
+                dora_indicators: indicators,
+                uradora_indicators: Vec::new(),
+                num_akadora: 0,
+                special_win: SpecialWin::None,
+            }
+        }
+
+        // Opt-in check (validate_input does not run it): one ura indicator under each
+        // revealed dora indicator, kan dora included. No ura indicators is fine (no riichi).
+        pub fn validate_indicators(&self) -> Result<(), &'static str> {
+            let num_ura = self.uradora_indicators.len();
+            if num_ura != 0 && num_ura != self.dora_indicators.len() {
+                return Err(
+                    "Invalid dora indicators: uradora_indicators must match dora_indicators in number.",
+                );
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    // Table counters carried from one hand to the next
+    pub struct TableState {
+        pub honba: u8,      // 本場 (Honba counter)
+        pub riichi_bou: u8, // リーチ棒 (Riichi sticks on the table)
+    }
+
+    impl TableState {
+        // a player declared riichi and put a stick on the table
+        pub fn on_riichi(&mut self) {
+            self.riichi_bou = self.riichi_bou.saturating_add(1);
+        }
+
+        // honba goes up when the dealer wins and resets otherwise. The sticks on
+        // the table are returned and cleared if winner_gets_sticks (on a double
+        // ron only one winner takes them); otherwise 0 and they stay.
+        pub fn on_win(&mut self, dealer_won: bool, winner_gets_sticks: bool) -> u8 {
+            self.honba = if dealer_won {
+                self.honba.saturating_add(1)
+            } else {
+                0
+            };
+            if winner_gets_sticks {
+                core::mem::take(&mut self.riichi_bou)
+            } else {
+                0
+            }
+        }
+
+        // exhaustive draw: honba goes up whether or not the dealer was tenpai,
+        // and the sticks stay on the table for the next winner.
+        // Returns whether the dealer keeps the seat (renchan), i.e. dealer_tenpai.
+        pub fn on_draw(&mut self, dealer_tenpai: bool) -> bool {
+            self.honba = self.honba.saturating_add(1);
+            dealer_tenpai
+        }
+    }
+}
+
+pub mod rules {
+    use super::tiles::Kaze;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    // How a Pinfu hand won by Tsumo is scored
+    pub enum PinfuTsumo {
+        TwentyFu, // Pinfu + MenzenTsumo at 20 fu (the usual rule)
+        ThirtyFu, // Pinfu + MenzenTsumo, but the 2 tsumo fu still count (30 fu)
+        NoPinfu,  // no Pinfu on a Tsumo win; scored like any other closed tsumo
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    // Fu for a pair of a wind that is both the round and the seat wind
+    pub enum DoubleWindPair {
+        FourFu, // 2 for each wind (the usual rule)
+        TwoFu,  // counted once, like any other value pair
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    // Configurable rule variations
+    pub struct Ruleset {
+        pub double_wind_pair_fu: DoubleWindPair, // 連風牌 (Double wind pair fu)
+        pub kiriage_mangan: bool, // 切り上げ満貫 (Round 4han30fu / 3han60fu up to mangan)
+        pub check_dealer_seat: bool, // 親 (Reject is_oya unless the seat wind is East)
+        pub max_bakaze: Kaze,     // 場風 (Last round wind: Nan for hanchan, Pei for longer)
+        pub daichisei: bool,      // 大七星 (Seven honor pairs as a yakuman)
+        pub sanrenkou: bool,      // 三連刻 (Three consecutive same-suit triplets, 2 han)
+        pub suurenkou: bool,      // 四連刻 (Four consecutive same-suit triplets, yakuman)
+        pub open_riichi: bool,    // オープン立直 (Riichi with the hand shown, +1 han)
+        pub aka_per_suit: [u8; 3], // 赤ドラ (Red fives in man, pin, sou)
+        pub max_yakuman_stack: Option<u8>, // 複合役満 (Cap on stacked yakuman, None = unlimited)
+        pub max_counter: u8,      // 本場/供託 (Largest honba or riichi stick count accepted)
+        pub pinfu_tsumo: PinfuTsumo, // 平和自摸 (Pinfu Tsumo scoring)
+        pub require_yaku: bool,   // 役 (false: non-standard house rule letting dora alone win)
+        pub require_dora_indicator: bool, // ドラ表示牌 (false: accept hands entered before any indicator is shown)
+    }
+
+    impl Default for Ruleset {
+        fn default() -> Self {
+            Self {
+                double_wind_pair_fu: DoubleWindPair::FourFu,
+                kiriage_mangan: false,
+                check_dealer_seat: true,
+                max_bakaze: Kaze::Nan,
+                daichisei: false,
+                sanrenkou: false,
+                suurenkou: false,
+                open_riichi: false,
+                aka_per_suit: [1, 1, 1],
+                max_yakuman_stack: None,
+                max_counter: 20,
+                pinfu_tsumo: PinfuTsumo::TwentyFu,
+                require_yaku: true,
+                require_dora_indicator: true,
+            }
+        }
     }
 }
 
 pub mod yaku {
-    use std::fmt;
+    use core::fmt;
 
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub enum Yaku {
         // 1 Han Yaku
         Riichi,           // 立直 (Riichi)
+        OpenRiichi,       // オープン立直 (Open Riichi, local rule, stacks on Riichi)
         Ippatsu,          // 一発 (Ippatsu)
         MenzenTsumo,      // 門前清自摸和 (Fully Concealed Hand)
         Pinfu,            // 平和 (No-Points Hand)
@@ -254,6 +629,7 @@ pub mod yaku {
         Sankantsu,      // 三槓子 (Three Quads)
         Shousangen,     // 小三元 (Little Three Dragons)
         Honroutou,      // 混老頭 (All Terminals and Honors)
+        Sanrenkou,      // 三連刻 (Three Consecutive Triplets, local rule)
 
         // 3 Han Yaku
         Ryanpeikou, // 二盃口 (Twice Pure Double Sequence)
@@ -280,6 +656,8 @@ pub mod yaku {
         SuuankouTanki,        // 四暗刻単騎 (Single Wait Four Concealed)
         KokushiMusouJusanmen, // 国士無S双13面待ち (13-Sided Wait Kokushi)
         JunseiChuurenPoutou,  // 純正九蓮宝燈 (True Nine Gates)
+        Daichisei,            // 大七星 (Big Seven Stars, local rule)
+        Suurenkou,            // 四連刻 (Four Consecutive Triplets, local rule)
 
         // Dora (not Yaku)
         Dora,    // ドラ (Dora)
@@ -287,6 +665,17 @@ pub mod yaku {
         AkaDora, // 赤ドラ (Red Five Dora)
     }
 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    // reference data for one yaku (see all_yaku)
+    pub struct YakuInfo {
+        pub yaku: Yaku,
+        pub closed_han: u8, // 13 per yakuman multiple
+        pub open_han: u8,   // 0 if the yaku needs a closed hand
+        pub is_yakuman: bool,
+        pub name_en: &'static str,
+        pub name_jp: &'static str,
+    }
+
     // terminal version of Display for Yaku. Not used in GUI
     impl fmt::Display for Yaku {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -297,17 +686,24 @@ pub mod yaku {
 
 pub mod scoring {
     use super::game::AgariType;
+    use super::hand::{HandStructure, Machi};
+    use super::tiles::Hai;
     use super::yaku::Yaku;
-    use std::fmt;
+    use alloc::vec::Vec;
+    use core::fmt;
 
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[derive(Debug, Clone, PartialEq, Eq)]
     // named point limits
     pub enum HandLimit {
-        Mangan,    // 満貫
-        Haneman,   // 跳満
-        Baiman,    // 倍満
-        Sanbaiman, // 三倍満
-        Yakuman,   // 役満 (13han+)
+        Mangan,        // 満貫
+        Haneman,       // 跳満
+        Baiman,        // 倍満
+        Sanbaiman,     // 三倍満
+        Yakuman,       // 役満 (a yakuman yaku)
+        KazoeYakuman,  // 数え役満 (13han+ counted from regular yaku and dora)
+        DoubleYakuman, // ダブル役満 (2 yakuman multiples)
+        TripleYakuman, // トリプル役満 (3 yakuman multiples)
     }
 
     // terminal version of Display for HandLimit. Not used in GUI
@@ -317,7 +713,7 @@ pub mod scoring {
         }
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     // complete scoring result for a winning hand
     pub struct AgariResult {
         pub han: u8,              // 飜 (Han count)
@@ -332,10 +728,90 @@ pub mod scoring {
         pub base_points: u32,
         pub oya_payment: u32,
         pub ko_payment: u32,
-        pub total_payment: u32,
+        pub total_payment: u32, // includes honba_bonus
         pub honba: u8,
+        pub honba_bonus: u32, // 積み棒 (Honba part of total_payment)
+        pub riichi_bou: u8,   // 供託 (Riichi sticks on the table, not in total_payment)
         pub agari_type: AgariType,
         pub is_oya: bool,
+        pub is_menzen: bool,                  // 門前 (Closed hand)
+        pub structure: Option<HandStructure>, // reading that was scored (None if not recorded)
+    }
+
+    impl AgariResult {
+        // yakuman (including kazoe yakuman)
+        pub fn is_yakuman(&self) -> bool {
+            matches!(
+                self.limit_name,
+                Some(
+                    HandLimit::Yakuman
+                        | HandLimit::KazoeYakuman
+                        | HandLimit::DoubleYakuman
+                        | HandLimit::TripleYakuman
+                )
+            )
+        }
+
+        // yakuman reached by counting han rather than by a yakuman yaku
+        pub fn is_kazoe_yakuman(&self) -> bool {
+            self.limit_name == Some(HandLimit::KazoeYakuman)
+        }
+
+        // mangan or above
+        pub fn is_limit(&self) -> bool {
+            self.limit_name.is_some()
+        }
+
+        // yaku (or dora kind) is in yaku_list
+        pub fn has_yaku(&self, yaku: Yaku) -> bool {
+            self.yaku_list.contains(&yaku)
+        }
+
+        // copies of one dora kind (Dora, UraDora or AkaDora) in yaku_list
+        pub fn dora_count(&self, kind: Yaku) -> u8 {
+            self.yaku_list.iter().filter(|y| **y == kind).count() as u8
+        }
+
+        // dora + ura dora + aka dora
+        pub fn total_dora(&self) -> u8 {
+            self.yaku_list
+                .iter()
+                .filter(|y| matches!(y, Yaku::Dora | Yaku::UraDora | Yaku::AkaDora))
+                .count() as u8
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    // how a result was reached, for explanations
+    pub struct Analysis {
+        pub is_menzen: bool,
+        pub agari_type: AgariType,
+        pub winning_tile: Hai,
+        pub machi: Machi,                        // 待ち (The wait type)
+        pub yaku_han: Vec<(Yaku, u8)>,           // han of each yaku, in yaku_list order
+        pub fu_sources: Vec<(&'static str, u8)>, // fu before rounding up (empty for yakuman)
+    }
+
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    // everything about a scored hand in one flat struct, e.g. for a JSON response
+    pub struct ScoreReport {
+        pub han: u8,
+        pub yaku_han: u8, // han from yaku (13 per yakuman)
+        pub dora_han: u8, // han from dora, ura dora and aka dora
+        pub fu: u8,
+        pub fu_sources: Vec<(&'static str, u8)>, // fu before rounding up (empty for yakuman)
+        pub limit_name: Option<HandLimit>,
+        pub total_payment: u32, // includes honba_bonus
+        pub oya_payment: u32,
+        pub ko_payment: u32,
+        pub honba_bonus: u32,
+        pub riichi_bou: u8,
+        pub agari_type: AgariType,
+        pub winning_tile: Hai,
+        pub machi: Machi,
+        pub yaku: Vec<(Yaku, u8)>, // han of each yaku, in yaku_list order
+        pub is_menzen: bool,
     }
 
     // terminal version of Display for AgariResult. Not used in GUI
@@ -345,12 +821,15 @@ pub mod scoring {
 
             // 1. Total Score
             writeln!(f, "Total Payment: {}", self.total_payment)?;
+            writeln!(f, "{}", if self.is_menzen { "Closed" } else { "Open" })?;
 
             // 2. Han / Fu & 4. Limit Name
             if let Some(limit) = &self.limit_name {
-                if limit == &HandLimit::Yakuman {
+                // kazoe yakuman keeps its han and fu, like the lower limits
+                if self.is_yakuman() && !self.is_kazoe_yakuman() {
+                    // Double and Triple are named; 4+ multiples print as Yakuman xN
                     let num_yakuman = self.han / 13;
-                    if num_yakuman > 1 {
+                    if num_yakuman > 3 {
                         writeln!(f, "\n{} x{}", limit, num_yakuman)?;
                     } else {
                         writeln!(f, "\n{}", limit)?;
@@ -453,20 +932,75 @@ pub mod scoring {
 pub mod input {
     use super::game::{AgariType, GameContext, PlayerContext};
     use super::hand::MentsuType;
-    use super::tiles::Hai;
+    use super::tiles::{Hai, Kaze, index_to_tile, tile_to_index};
+    use alloc::{vec, vec::Vec};
 
-    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct OpenMeldInput {
         // type of meld
         pub mentsu_type: MentsuType,
 
         // single tile that uniquely identifies the meld
         pub representative_tile: Hai,
+
+        // seat wind of the player it was called from (for pao), if known
+        pub called_from: Option<Kaze>,
     }
 
-    #[derive(Debug, Clone)]
+    impl OpenMeldInput {
+        // called triplet (pon) of tile
+        pub fn pon(tile: Hai) -> Self {
+            Self {
+                mentsu_type: MentsuType::Koutsu,
+                representative_tile: tile,
+                called_from: None,
+            }
+        }
+
+        // called sequence (chi) starting at low_tile, e.g. 3m for 345m
+        pub fn chi(low_tile: Hai) -> Self {
+            Self {
+                mentsu_type: MentsuType::Shuntsu,
+                representative_tile: low_tile,
+                called_from: None,
+            }
+        }
+
+        // open quad (daiminkan or shouminkan) of tile
+        pub fn kan(tile: Hai) -> Self {
+            Self {
+                mentsu_type: MentsuType::Kantsu,
+                representative_tile: tile,
+                called_from: None,
+            }
+        }
+
+        // tiles of the meld (a Chi that runs past 9 is truncated)
+        pub fn tiles(&self) -> Vec<Hai> {
+            let rep_tile = self.representative_tile;
+            match self.mentsu_type {
+                MentsuType::Shuntsu => {
+                    let start_idx = tile_to_index(&rep_tile);
+                    if start_idx >= 27 {
+                        return vec![rep_tile];
+                    }
+                    let suit_end = (start_idx / 9) * 9 + 9;
+                    (start_idx..(start_idx + 3).min(suit_end))
+                        .map(index_to_tile)
+                        .collect()
+                }
+                MentsuType::Koutsu => vec![rep_tile; 3],
+                MentsuType::Kantsu => vec![rep_tile; 4],
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct UserInput {
-        // Hand Composition
+        // Hand Composition: concealed tiles only (no open meld or closed kan tiles).
+        // Includes the winning tile on Tsumo, excludes it on Ron.
         pub hand_tiles: Vec<Hai>,
 
         pub winning_tile: Hai,
@@ -481,4 +1015,358 @@ pub mod input {
         pub game_context: GameContext,
         pub agari_type: AgariType,
     }
+
+    impl UserInput {
+        // build from the concealed tiles before the win (never including winning_tile);
+        // the winning tile is added to hand_tiles on Tsumo, as hand_tiles expects
+        pub fn from_parts(
+            concealed: Vec<Hai>,
+            winning_tile: Hai,
+            agari_type: AgariType,
+            open_melds: Vec<OpenMeldInput>,
+            closed_kans: Vec<Hai>,
+            player_context: PlayerContext,
+            game_context: GameContext,
+        ) -> Self {
+            let mut hand_tiles = concealed;
+            if agari_type == AgariType::Tsumo {
+                hand_tiles.push(winning_tile);
+            }
+            Self {
+                hand_tiles,
+                winning_tile,
+                open_melds,
+                closed_kans,
+                player_context,
+                game_context,
+                agari_type,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::game::{AgariType, GameContext, SpecialWin, SpecialWinFlags, TableState};
+    use super::hand::Machi;
+    use super::hand::{HandGroup, HandOrganization};
+    use super::input::{OpenMeldInput, UserInput};
+    use super::scoring::HandLimit;
+    use super::tiles::{
+        GREEN_MASK, HONOR_MASK, Hai, Jihai, Kaze, Sangenpai, TERMINAL_HONOR_MASK, TERMINAL_MASK,
+        counts_only_in, hand_fingerprint, index_to_tile, tile_to_index,
+    };
+    use super::yaku::Yaku;
+    use crate::implements::organize_hand;
+    use crate::implements::test_hands::{counts, hand, tile, tiles};
+    use crate::implements::{calculate_agari, points_from_han_fu};
+    use alloc::format;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn table_state_dealer_win_adds_honba() {
+        let mut table = TableState {
+            honba: 1,
+            riichi_bou: 2,
+        };
+        assert_eq!(table.on_win(true, true), 2);
+        assert_eq!(
+            table,
+            TableState {
+                honba: 2,
+                riichi_bou: 0
+            }
+        );
+    }
+
+    #[test]
+    fn table_state_non_dealer_win_resets_honba() {
+        let mut table = TableState {
+            honba: 3,
+            riichi_bou: 1,
+        };
+        assert_eq!(table.on_win(false, true), 1);
+        assert_eq!(table, TableState::default());
+
+        // a second winner of the same discard leaves the sticks alone
+        let mut table = TableState {
+            honba: 0,
+            riichi_bou: 1,
+        };
+        assert_eq!(table.on_win(false, false), 0);
+        assert_eq!(table.riichi_bou, 1);
+    }
+
+    #[test]
+    fn table_state_draw_keeps_sticks() {
+        let mut table = TableState::default();
+        table.on_riichi();
+        assert!(!table.on_draw(false));
+        assert!(table.on_draw(true));
+        assert_eq!(
+            table,
+            TableState {
+                honba: 2,
+                riichi_bou: 1
+            }
+        );
+    }
+
+    #[test]
+    fn special_win_rejects_combined_flags() {
+        // Tenhou + Haitei and Rinshan + Chankan were both expressible as flags
+        let tenhou_haitei = SpecialWinFlags {
+            is_tenhou: true,
+            is_haitei: true,
+            ..SpecialWinFlags::default()
+        };
+        let rinshan_chankan = SpecialWinFlags {
+            is_rinshan: true,
+            is_chankan: true,
+            ..SpecialWinFlags::default()
+        };
+        for flags in [tenhou_haitei, rinshan_chankan] {
+            assert_eq!(
+                SpecialWin::try_from(flags),
+                Err("Invalid game state: at most one special win condition can apply.")
+            );
+        }
+    }
+
+    #[test]
+    fn special_win_round_trips_through_flags() {
+        let all = [
+            SpecialWin::None,
+            SpecialWin::Tenhou,
+            SpecialWin::Chiihou,
+            SpecialWin::Renhou,
+            SpecialWin::Haitei,
+            SpecialWin::Houtei,
+            SpecialWin::Rinshan,
+            SpecialWin::Chankan,
+        ];
+        for win in all {
+            assert_eq!(SpecialWin::try_from(SpecialWinFlags::from(win)), Ok(win));
+        }
+        assert_eq!(
+            SpecialWinFlags::from(SpecialWin::Houtei),
+            SpecialWinFlags {
+                is_houtei: true,
+                ..SpecialWinFlags::default()
+            }
+        );
+    }
+
+    #[test]
+    fn mangan_is_a_limit_but_not_yakuman() {
+        let mangan = points_from_han_fu(5, 30, false, AgariType::Ron, 0);
+        assert!(mangan.is_limit());
+        assert!(!mangan.is_yakuman());
+
+        let below = points_from_han_fu(3, 30, false, AgariType::Ron, 0);
+        assert!(!below.is_limit());
+    }
+
+    #[test]
+    fn daisangen_is_yakuman() {
+        let result = calculate_agari(&hand("555666777z123m99p", "9p", false)).unwrap();
+        assert!(result.is_yakuman());
+        assert!(result.is_limit());
+        assert!(!result.is_kazoe_yakuman());
+    }
+
+    #[test]
+    fn each_wait_has_its_fu_and_label() {
+        let waits = [
+            (Machi::Ryanmen, 0, "two-sided"),
+            (Machi::Tanki, 2, "pair"),
+            (Machi::Penchan, 2, "edge"),
+            (Machi::Kanchan, 2, "closed"),
+            (Machi::Shanpon, 0, "dual-pon"),
+            (Machi::KokushiIchimen, 0, "thirteen orphans single"),
+            (Machi::KokushiJusanmen, 0, "thirteen orphans 13-sided"),
+        ];
+        for (machi, fu, label) in waits {
+            assert_eq!(machi.fu(), fu, "{label}");
+            assert_eq!(machi.to_string(), label);
+        }
+    }
+
+    #[test]
+    fn total_dora_counts_all_three_kinds() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.player_context.is_riichi = true;
+        input.game_context.dora_indicators = tiles("1m");
+        input.game_context.uradora_indicators = tiles("2m");
+        input.game_context.num_akadora = 1;
+        let result = calculate_agari(&input).unwrap();
+
+        assert_eq!(result.total_dora(), 3);
+    }
+
+    #[test]
+    fn masks_match_the_tile_predicates() {
+        for i in 0..34 {
+            let tile = index_to_tile(i);
+            assert_eq!(TERMINAL_MASK[i], tile.is_terminal(), "{i}");
+            assert_eq!(HONOR_MASK[i], tile.is_jihai(), "{i}");
+            assert_eq!(TERMINAL_HONOR_MASK[i], tile.is_yaochuu(), "{i}");
+        }
+        let green = counts("23468s6z");
+        for i in 0..34 {
+            assert_eq!(GREEN_MASK[i], green[i] > 0, "{i}");
+        }
+    }
+
+    #[test]
+    fn counts_only_in_checks_every_tile() {
+        assert!(counts_only_in(&counts("223344666888s66z"), &GREEN_MASK));
+        assert!(!counts_only_in(&counts("223344666888s55z"), &GREEN_MASK));
+        assert!(counts_only_in(
+            &counts("19m19p19s1234567z"),
+            &TERMINAL_HONOR_MASK
+        ));
+        assert!(!counts_only_in(
+            &counts("19m19p19s1234567z"),
+            &TERMINAL_MASK
+        ));
+        assert!(counts_only_in(&[0; 34], &HONOR_MASK));
+    }
+
+    #[test]
+    fn example_hand_reports_closed() {
+        let closed = calculate_agari(&hand("234567m345678p44s", "8p", false)).unwrap();
+        assert!(closed.is_menzen);
+        assert!(format!("{}", closed).contains("\nClosed\n"));
+
+        let mut input = hand("567m345678p44s", "8p", false);
+        input.open_melds = vec![OpenMeldInput::chi(tile("2m"))];
+        input.player_context.is_menzen = false;
+        let open = calculate_agari(&input).unwrap();
+        assert!(!open.is_menzen);
+        assert!(format!("{}", open).contains("\nOpen\n"));
+    }
+
+    #[test]
+    fn jihai_all_is_winds_then_dragons_in_index_order() {
+        let all = Jihai::all();
+        for (i, jihai) in all.iter().enumerate() {
+            assert_eq!(tile_to_index(&Hai::Jihai(*jihai)), 27 + i);
+        }
+        assert_eq!(all[..4], Kaze::all().map(Jihai::Kaze));
+        assert_eq!(all[4..], Sangenpai::all().map(Jihai::Sangen));
+        assert_eq!(all.map(Hai::Jihai).to_vec(), tiles("1234567z"));
+    }
+
+    #[test]
+    fn kazoe_and_shape_yakuman_are_labelled_apart() {
+        let mut counted = hand("22334455667788m", "8m", false);
+        counted.player_context.is_riichi = true;
+        counted.game_context.dora_indicators = tiles("1m");
+        let kazoe = calculate_agari(&counted).unwrap();
+        assert_eq!(kazoe.han, 14);
+        assert_eq!(kazoe.limit_name, Some(HandLimit::KazoeYakuman));
+        assert!(kazoe.is_kazoe_yakuman());
+        let text = format!("{}", kazoe);
+        assert!(
+            text.contains(&format!("KazoeYakuman ({} Fu, 14 Han)", kazoe.fu)),
+            "{text}"
+        );
+
+        let mut open = hand("555666777z11s", "1s", false);
+        open.open_melds = vec![OpenMeldInput::chi(tile("6p"))];
+        open.player_context.is_menzen = false;
+        let daisangen = calculate_agari(&open).unwrap();
+        assert_eq!(daisangen.limit_name, Some(HandLimit::Yakuman));
+        assert!(!daisangen.is_kazoe_yakuman());
+        let text = format!("{}", daisangen);
+        assert!(text.contains("\nYakuman\n"), "{text}");
+    }
+
+    #[test]
+    fn groups_are_four_melds_then_the_pair() {
+        let Ok(HandOrganization::YonmentsuIchiatama(agari)) =
+            organize_hand(&hand("234567m345678p44s", "8p", false))
+        else {
+            panic!("a standard hand");
+        };
+
+        let groups: Vec<HandGroup> = agari.groups().collect();
+        assert_eq!(groups.len(), 5);
+        assert!(groups[..4].iter().all(|g| matches!(g, HandGroup::Meld(_))));
+        assert!(matches!(groups[4], HandGroup::Pair(t) if t == tile("4s")));
+    }
+
+    #[test]
+    fn fingerprint_ignores_tile_order() {
+        let sorted = tiles("234567m345678p44s");
+        let mut shuffled = sorted.clone();
+        shuffled.reverse();
+        shuffled.swap(0, 7);
+        assert_ne!(sorted, shuffled);
+        assert_eq!(hand_fingerprint(&sorted), hand_fingerprint(&shuffled));
+
+        // one tile different is a different hand
+        assert_ne!(
+            hand_fingerprint(&sorted),
+            hand_fingerprint(&tiles("234567m345678p45s"))
+        );
+    }
+
+    #[test]
+    fn has_yaku_and_dora_count() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        // 3s indicator: the 4s pair is two dora
+        input.game_context.dora_indicators = tiles("3s");
+        let result = calculate_agari(&input).unwrap();
+
+        assert!(result.has_yaku(Yaku::Pinfu));
+        assert!(!result.has_yaku(Yaku::Toitoi));
+        assert_eq!(result.dora_count(Yaku::Dora), 2);
+        assert_eq!(result.dora_count(Yaku::AkaDora), 0);
+    }
+
+    #[test]
+    fn ura_indicators_must_match_dora_indicators() {
+        let mut context = GameContext::with_dora(tiles("3s1z"));
+        // no riichi, no ura indicators
+        assert!(context.validate_indicators().is_ok());
+        context.uradora_indicators = tiles("5m");
+        assert!(context.validate_indicators().is_err());
+        context.uradora_indicators = tiles("5m9p");
+        assert!(context.validate_indicators().is_ok());
+        context.uradora_indicators = tiles("5m9p2z");
+        assert!(context.validate_indicators().is_err());
+    }
+
+    #[test]
+    fn from_parts_scores_like_hand_tiles() {
+        for agari_type in [AgariType::Tsumo, AgariType::Ron] {
+            let tsumo = agari_type == AgariType::Tsumo;
+            let expected = hand("234567m345678p44s", "8p", tsumo);
+            let input = UserInput::from_parts(
+                tiles("234567m34567p44s"),
+                tile("8p"),
+                agari_type,
+                Vec::new(),
+                Vec::new(),
+                expected.player_context,
+                expected.game_context.clone(),
+            );
+            assert_eq!(
+                calculate_agari(&input).unwrap(),
+                calculate_agari(&expected).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn tiles_to_unicode_glyphs() {
+        assert_eq!(tile("1m").to_unicode(), '🀇');
+        assert_eq!(tile("1z").to_unicode(), '🀀');
+        assert_eq!(tile("9s").to_unicode(), '🀘');
+        assert_eq!(tile("5z").to_unicode(), '🀆');
+    }
 }
@@ -1,12 +1,17 @@
 // yaku_checker.rs: Check for yaku
 
+use super::raw_hand_organizer::organize_hand;
+use super::shanten_calculator::diagnose;
 use super::types::{
-    game::{AgariType, GameContext, PlayerContext},
+    game::{AgariType, GameContext, PlayerContext, SpecialWin},
     hand::{AgariHand, HandOrganization, HandStructure, Machi, Mentsu, MentsuType},
-    tiles::{Hai, Jihai, Kaze, Sangenpai, Suhai, index_to_tile, tile_to_index},
+    input::UserInput,
+    rules::{PinfuTsumo, Ruleset},
+    tiles::{GREEN_MASK, Hai, Jihai, Kaze, Sangenpai, Suhai, index_to_tile, tile_to_index},
     yaku::Yaku,
 };
-use std::collections::{HashMap, HashSet};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::{vec, vec::Vec};
 
 #[derive(Debug, Clone)]
 pub struct YakuResult {
@@ -21,19 +26,21 @@ pub fn check_all_yaku(
     player: &PlayerContext,
     game: &GameContext,
     agari_type: AgariType,
+    rules: &Ruleset,
 ) -> Result<YakuResult, &'static str> {
     // game-state Yakuman
     let mut yakuman_list = check_game_state_yakuman(player, game);
 
     // hand-based Yakuman
     let (hand_structure, hand_yakuman) =
-        match resolve_hand_structure(organization, player, game, agari_type) {
+        match resolve_hand_structure(organization, player, game, agari_type, rules) {
             Ok((structure, yakuman)) => (structure, yakuman),
             Err(e) => return Err(e),
         };
 
     yakuman_list.extend(hand_yakuman);
 
+    // Any yakuman (a blessing included) scores alone: no regular yaku or dora on top
     if !yakuman_list.is_empty() {
         let final_yakuman = post_process_yakuman(yakuman_list);
 
@@ -47,44 +54,51 @@ pub fn check_all_yaku(
     // regular yaku
     let mut regular_yaku: Vec<Yaku> = match &hand_structure {
         HandStructure::YonmentsuIchiatama(agari_hand) => {
-            find_standard_yaku(agari_hand, player, game, agari_type)
+            find_standard_yaku(agari_hand, player, game, agari_type, rules)
         }
         HandStructure::Chiitoitsu {
             pairs,
             agari_hai,
             machi,
-        } => find_chiitoitsu_yaku(pairs, agari_hai, machi, player, game, agari_type),
+        } => find_chiitoitsu_yaku(pairs, agari_hai, machi, player, game, agari_type, rules),
         _ => vec![],
     };
 
-    // Dora
+    // Dora alone cannot win (Riichi counts as a yaku), unless the ruleset allows it
     let has_yaku = !regular_yaku.is_empty() || player.is_riichi || player.is_daburu_riichi;
+    if !has_yaku && rules.require_yaku {
+        return Err("No Yaku Found: dora alone cannot win.");
+    }
 
+    // Dora
     let mut num_akadora_to_add = 0;
 
-    if has_yaku {
-        let all_tiles = get_all_tiles_from_structure(&hand_structure);
+    let all_tiles = get_all_tiles_from_structure(&hand_structure);
 
-        let dora_count = count_dora(&all_tiles, &game.dora_indicators);
-        for _ in 0..dora_count {
-            regular_yaku.push(Yaku::Dora);
-        }
+    let dora_count = count_dora(&all_tiles, &game.dora_indicators);
+    for _ in 0..dora_count {
+        regular_yaku.push(Yaku::Dora);
+    }
 
-        if (player.is_riichi || player.is_daburu_riichi) && !game.uradora_indicators.is_empty() {
-            let uradora_count = count_dora(&all_tiles, &game.uradora_indicators);
-            for _ in 0..uradora_count {
-                regular_yaku.push(Yaku::UraDora);
-            }
+    if (player.is_riichi || player.is_daburu_riichi) && !game.uradora_indicators.is_empty() {
+        let uradora_count = count_dora(&all_tiles, &game.uradora_indicators);
+        for _ in 0..uradora_count {
+            regular_yaku.push(Yaku::UraDora);
         }
+    }
 
-        if game.num_akadora > 0 {
-            num_akadora_to_add = game.num_akadora;
-            for _ in 0..game.num_akadora {
-                regular_yaku.push(Yaku::AkaDora);
-            }
+    if game.num_akadora > 0 {
+        num_akadora_to_add = game.num_akadora;
+        for _ in 0..game.num_akadora {
+            regular_yaku.push(Yaku::AkaDora);
         }
     }
 
+    // even without require_yaku, a hand needs at least 1 han from dora
+    if regular_yaku.is_empty() {
+        return Err("No Yaku Found: no yaku and no dora.");
+    }
+
     Ok(YakuResult {
         hand_structure,
         yaku_list: regular_yaku,
@@ -92,20 +106,43 @@ pub fn check_all_yaku(
     })
 }
 
+/// Standard, Kokushi or Chiitoitsu structure of a winning hand
+pub fn classify_hand(input: &UserInput) -> Result<HandStructure, &'static str> {
+    match organize_hand(input)? {
+        HandOrganization::YonmentsuIchiatama(agari_hand) => {
+            Ok(HandStructure::YonmentsuIchiatama(agari_hand))
+        }
+        HandOrganization::Irregular { counts, agari_hai } => {
+            if let Some((kokushi_structure, _)) = check_kokushi(&counts, agari_hai) {
+                Ok(kokushi_structure)
+            } else if let Some(chiitoitsu_structure) = check_chiitoitsu(&counts, agari_hai) {
+                Ok(chiitoitsu_structure)
+            } else {
+                Err(diagnose(&counts))
+            }
+        }
+    }
+}
+
+/// Seven distinct pairs and nothing else (four of a tile is not two pairs)
+pub fn is_chiitoitsu(counts: &[u8; 34]) -> bool {
+    counts.iter().all(|&c| c == 0 || c == 2) && counts.iter().filter(|&&c| c == 2).count() == 7
+}
+
+/// One of each terminal and honor plus a pair of one of them, containing agari_hai
+pub fn is_kokushi(counts: &[u8; 34], agari_hai: Hai) -> bool {
+    check_kokushi(counts, agari_hai).is_some()
+}
+
 // ---Yakuman Checkers---
 
 fn check_game_state_yakuman(_player: &PlayerContext, game: &GameContext) -> Vec<Yaku> {
-    let mut yaku = Vec::new();
-    if game.is_tenhou {
-        yaku.push(Yaku::Tenhou);
-    }
-    if game.is_chiihou {
-        yaku.push(Yaku::Chiihou);
+    match game.special_win {
+        SpecialWin::Tenhou => vec![Yaku::Tenhou],
+        SpecialWin::Chiihou => vec![Yaku::Chiihou],
+        SpecialWin::Renhou => vec![Yaku::Renhou],
+        _ => Vec::new(),
     }
-    if game.is_renhou {
-        yaku.push(Yaku::Renhou);
-    }
-    yaku
 }
 
 fn resolve_hand_structure(
@@ -113,11 +150,12 @@ fn resolve_hand_structure(
     player: &PlayerContext,
     game: &GameContext,
     agari_type: AgariType,
+    rules: &Ruleset,
 ) -> Result<(HandStructure, Vec<Yaku>), &'static str> {
     match org {
         HandOrganization::YonmentsuIchiatama(agari_hand) => {
             let (yakuman_list, chuuren_flag) =
-                check_standard_yakuman(&agari_hand, player, game, agari_type);
+                check_standard_yakuman(&agari_hand, player, game, agari_type, rules);
 
             let structure = if let Some(is_junsei) = chuuren_flag {
                 HandStructure::ChuurenPoutou {
@@ -137,10 +175,10 @@ fn resolve_hand_structure(
             }
             // Chiitoitsu
             else if let Some(chiitoitsu_structure) = check_chiitoitsu(&counts, agari_hai) {
-                let yakuman = check_chiitoitsu_yakuman(&chiitoitsu_structure);
+                let yakuman = check_chiitoitsu_yakuman(&chiitoitsu_structure, rules);
                 Ok((chiitoitsu_structure, yakuman))
             } else {
-                Err("No Yaku Found")
+                Err(diagnose(&counts))
             }
         }
     }
@@ -151,6 +189,7 @@ fn check_standard_yakuman(
     _player: &PlayerContext,
     _game: &GameContext,
     agari_type: AgariType,
+    rules: &Ruleset,
 ) -> (Vec<Yaku>, Option<bool>) {
     let mut yakuman = Vec::new();
     let all_tiles = get_all_tiles(hand);
@@ -186,7 +225,7 @@ fn check_standard_yakuman(
     let (_koutsu, kantsu) = count_koutsu_kantsu(hand);
     let concealed_koutsu = count_concealed_koutsu(hand, agari_type);
 
-    // Suukantsu
+    // Suukantsu: closed kans plus open kans (daiminkan and shouminkan are both open Kantsu)
     if kantsu == 4 {
         yakuman.push(Yaku::Suukantsu);
     }
@@ -200,6 +239,11 @@ fn check_standard_yakuman(
         }
     }
 
+    // Suurenkou (local rule)
+    if rules.suurenkou && count_renkou(hand) == 4 {
+        yakuman.push(Yaku::Suurenkou);
+    }
+
     // Daisangen
     let mut dragon_koutsu = 0;
     for mentsu in &hand.mentsu {
@@ -312,39 +356,25 @@ fn check_kokushi(counts: &[u8; 34], agari_hai: Hai) -> Option<(HandStructure, Ya
 }
 
 fn check_chiitoitsu(counts: &[u8; 34], agari_hai: Hai) -> Option<HandStructure> {
-    let mut pair_count = 0;
-    let mut pairs = Vec::new();
-
-    for (idx, &count) in counts.iter().enumerate() {
-        if count > 0 {
-            if count == 2 {
-                pair_count += 1;
-                let tile = index_to_tile(idx);
-                pairs.push((tile, tile));
-            } else if count == 4 {
-                pair_count += 2;
-                let tile = index_to_tile(idx);
-                pairs.push((tile, tile));
-                pairs.push((tile, tile));
-            } else {
-                return None;
-            }
-        }
+    if !is_chiitoitsu(counts) {
+        return None;
     }
+    let pairs: Vec<(Hai, Hai)> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count == 2)
+        .map(|(idx, _)| (index_to_tile(idx), index_to_tile(idx)))
+        .collect();
 
-    if pair_count == 7 {
-        Some(HandStructure::Chiitoitsu {
-            pairs: pairs.try_into().ok()?,
-            agari_hai,
-            machi: Machi::Tanki,
-        })
-    } else {
-        None
-    }
+    Some(HandStructure::Chiitoitsu {
+        pairs: pairs.try_into().ok()?,
+        agari_hai,
+        machi: Machi::Tanki,
+    })
 }
 
 /// Tsuuiisou
-fn check_chiitoitsu_yakuman(hand: &HandStructure) -> Vec<Yaku> {
+fn check_chiitoitsu_yakuman(hand: &HandStructure, rules: &Ruleset) -> Vec<Yaku> {
     if let HandStructure::Chiitoitsu { pairs, .. } = hand {
         let mut is_tsuuiisou = true;
         for (tile, _) in pairs {
@@ -353,6 +383,10 @@ fn check_chiitoitsu_yakuman(hand: &HandStructure) -> Vec<Yaku> {
                 break;
             }
         }
+        // Daichisei (local rule): the seven honor pairs, replaces Tsuuiisou
+        if is_tsuuiisou && rules.daichisei {
+            return vec![Yaku::Daichisei];
+        }
         if is_tsuuiisou {
             return vec![Yaku::Tsuuiisou];
         }
@@ -364,13 +398,18 @@ fn check_chiitoitsu_yakuman(hand: &HandStructure) -> Vec<Yaku> {
 fn check_chuuren(hand: &AgariHand) -> Option<bool> {
     let all_tiles = get_all_tiles(hand);
 
-    let (is_chinitsu, suit) = check_chinitsu(&all_tiles);
+    let (is_chinitsu, suit) = check_chinitsu(&tile_counts(&all_tiles));
     if !is_chinitsu {
         return None;
     }
     let suit = suit.unwrap();
 
-    if !hand.mentsu.iter().all(|m| !m.is_minchou) {
+    // Fully concealed, and no kan: a quad breaks the 1112345678999 + 1 shape
+    if hand
+        .mentsu
+        .iter()
+        .any(|m| m.is_minchou || m.mentsu_type == MentsuType::Kantsu)
+    {
         return None;
     }
 
@@ -407,6 +446,7 @@ fn check_chuuren(hand: &AgariHand) -> Option<bool> {
     if !has_extra {
         return None;
     }
+    // Junsei: the hand before the win was exactly 1112345678999 (nine-sided wait)
     if let Hai::Suhai(n, s) = hand.agari_hai {
         if s == suit && n as usize == extra_tile_num {
             return Some(true);
@@ -438,39 +478,30 @@ fn find_standard_yaku(
     player: &PlayerContext,
     game: &GameContext,
     agari_type: AgariType,
+    rules: &Ruleset,
 ) -> Vec<Yaku> {
     let mut yaku_list = Vec::new();
 
     // State-based Yaku
-    if player.is_daburu_riichi {
-        yaku_list.push(Yaku::DaburuRiichi);
-    } else if player.is_riichi {
-        yaku_list.push(Yaku::Riichi);
-    }
-    if player.is_ippatsu {
-        yaku_list.push(Yaku::Ippatsu);
-    }
+    yaku_list.extend(riichi_yaku(player, rules));
     if player.is_menzen && agari_type == AgariType::Tsumo {
         yaku_list.push(Yaku::MenzenTsumo);
     }
-    if game.is_haitei && agari_type == AgariType::Tsumo {
-        yaku_list.push(Yaku::HaiteiRaoyue);
-    }
-    if game.is_houtei && agari_type == AgariType::Ron {
-        yaku_list.push(Yaku::HouteiRaoyui);
-    }
-    if game.is_rinshan {
-        yaku_list.push(Yaku::RinshanKaihou);
-    }
-    if game.is_chankan {
-        yaku_list.push(Yaku::Chankan);
+    match (game.special_win, agari_type) {
+        (SpecialWin::Haitei, AgariType::Tsumo) => yaku_list.push(Yaku::HaiteiRaoyue),
+        (SpecialWin::Houtei, AgariType::Ron) => yaku_list.push(Yaku::HouteiRaoyui),
+        (SpecialWin::Rinshan, _) => yaku_list.push(Yaku::RinshanKaihou),
+        (SpecialWin::Chankan, _) => yaku_list.push(Yaku::Chankan),
+        _ => {}
     }
 
     // Yakuhai
     yaku_list.extend(check_yakuhai(hand, player, game));
 
     // Pinfu
-    if check_pinfu(hand, player, game) {
+    let pinfu_allowed =
+        !(agari_type == AgariType::Tsumo && rules.pinfu_tsumo == PinfuTsumo::NoPinfu);
+    if pinfu_allowed && check_pinfu(hand, player, game) {
         yaku_list.push(Yaku::Pinfu);
     }
 
@@ -486,6 +517,8 @@ fn find_standard_yaku(
         .filter(|m| m.mentsu_type == MentsuType::Shuntsu)
         .collect();
 
+    // Ryanpeikou replaces Iipeikou. The same tiles also read as Chiitoitsu,
+    // but that reading always scores lower, so calculate_agari keeps this one.
     if player.is_menzen {
         let (iipeikou, ryanpeikou) = check_peikou(&shuntsu);
         if ryanpeikou {
@@ -508,13 +541,13 @@ fn find_standard_yaku(
 
     if koutsu + kantsu == 4 {
         yaku_list.push(Yaku::Toitoi);
-    } else {
-        let concealed_koutsu = count_concealed_koutsu(hand, agari_type);
-        if concealed_koutsu == 3 {
-            yaku_list.push(Yaku::Sanankou);
-        }
+    }
+    // stacks with Toitoi, e.g. three concealed triplets and one called or won by Ron
+    if count_concealed_koutsu(hand, agari_type) == 3 {
+        yaku_list.push(Yaku::Sanankou);
     }
 
+    // Sankantsu: open or closed, counted the same way as Suukantsu
     if kantsu == 3 {
         yaku_list.push(Yaku::Sankantsu);
     }
@@ -523,6 +556,13 @@ fn find_standard_yaku(
         yaku_list.push(Yaku::SanshokuDoukou);
     }
 
+    // Sanrenkou (local rule), stacks with Toitoi/Sanankou
+    if rules.sanrenkou && count_renkou(hand) >= 3 {
+        yaku_list.push(Yaku::Sanrenkou);
+    }
+
+    // Shousangen stacks with the two dragon triplets' Yakuhai (check_yakuhai);
+    // the third dragon is the pair and earns no Yakuhai
     if check_shousangen(hand) {
         yaku_list.push(Yaku::Shousangen);
     }
@@ -534,6 +574,8 @@ fn find_standard_yaku(
     let is_honroutou =
         all_tiles.iter().all(|t| t.is_yaochuu()) && !all_tiles.iter().all(|t| t.is_terminal()); // Exclude Chinroutou
 
+    // Exclusive: Honroutou (no sequences, so Toitoi comes with it) replaces Chanta
+    // and is worth at least as much; Junchan is Chanta without honors and replaces it
     if is_honroutou {
         yaku_list.push(Yaku::Honroutou);
     } else {
@@ -546,11 +588,12 @@ fn find_standard_yaku(
     }
 
     //  Color Yaku
-    let (is_chinitsu, _) = check_chinitsu(&all_tiles);
+    let counts = tile_counts(&all_tiles);
+    let (is_chinitsu, _) = check_chinitsu(&counts);
     if is_chinitsu {
         yaku_list.push(Yaku::Chinitsu);
     } else {
-        let (is_honitsu, _) = check_honitsu(&all_tiles);
+        let (is_honitsu, _) = check_honitsu(&counts);
         if is_honitsu {
             yaku_list.push(Yaku::Honitsu);
         }
@@ -572,30 +615,23 @@ fn find_chiitoitsu_yaku(
     player: &PlayerContext,
     game: &GameContext,
     agari_type: AgariType,
+    rules: &Ruleset,
 ) -> Vec<Yaku> {
     let mut yaku_list = Vec::new();
 
     yaku_list.push(Yaku::Chiitoitsu);
 
-    // Riichi/DaburuRiichi/Ippatsu
-    if player.is_daburu_riichi {
-        yaku_list.push(Yaku::DaburuRiichi);
-    } else if player.is_riichi {
-        yaku_list.push(Yaku::Riichi);
-    }
-    if player.is_ippatsu {
-        yaku_list.push(Yaku::Ippatsu);
-    }
+    // Riichi/DaburuRiichi/OpenRiichi/Ippatsu
+    yaku_list.extend(riichi_yaku(player, rules));
     // MenzenTsumo
     if agari_type == AgariType::Tsumo {
         yaku_list.push(Yaku::MenzenTsumo);
     }
     // Haitei/Houtei
-    if game.is_haitei && agari_type == AgariType::Tsumo {
-        yaku_list.push(Yaku::HaiteiRaoyue);
-    }
-    if game.is_houtei && agari_type == AgariType::Ron {
-        yaku_list.push(Yaku::HouteiRaoyui);
+    match (game.special_win, agari_type) {
+        (SpecialWin::Haitei, AgariType::Tsumo) => yaku_list.push(Yaku::HaiteiRaoyue),
+        (SpecialWin::Houtei, AgariType::Ron) => yaku_list.push(Yaku::HouteiRaoyui),
+        _ => {}
     }
 
     // Tile-based Yaku
@@ -610,12 +646,13 @@ fn find_chiitoitsu_yaku(
         yaku_list.push(Yaku::Honroutou);
     }
 
-    // Color Yaku
-    let (is_chinitsu, _) = check_chinitsu(&all_tiles);
+    // Color Yaku (from counts, as there is no AgariHand for seven pairs)
+    let counts = tile_counts(&all_tiles);
+    let (is_chinitsu, _) = check_chinitsu(&counts);
     if is_chinitsu {
         yaku_list.push(Yaku::Chinitsu);
     } else {
-        let (is_honitsu, _) = check_honitsu(&all_tiles);
+        let (is_honitsu, _) = check_honitsu(&counts);
         if is_honitsu {
             yaku_list.push(Yaku::Honitsu);
         }
@@ -665,7 +702,7 @@ fn get_dora_tile(indicator: &Hai) -> Hai {
 fn check_yakuhai(hand: &AgariHand, player: &PlayerContext, game: &GameContext) -> Vec<Yaku> {
     let mut yaku = Vec::new();
 
-    let koutsu_tiles: HashSet<Hai> = hand
+    let koutsu_tiles: BTreeSet<Hai> = hand
         .mentsu
         .iter()
         .filter(|m| is_koutsu_or_kantsu(m))
@@ -740,7 +777,7 @@ fn check_peikou<'a>(shuntsu: &[&'a Mentsu]) -> (bool, bool) {
     }
 
     let mut identical_pairs = 0;
-    let mut seen = HashSet::new();
+    let mut seen = BTreeSet::new();
 
     for (i, m1) in shuntsu.iter().enumerate() {
         if seen.contains(&i) {
@@ -768,7 +805,7 @@ fn check_sanshoku_doujun<'a>(shuntsu: &[&'a Mentsu]) -> bool {
         return false;
     }
 
-    let mut starters: HashMap<u8, (bool, bool, bool)> = HashMap::new();
+    let mut starters: BTreeMap<u8, (bool, bool, bool)> = BTreeMap::new();
 
     for m in shuntsu {
         if let Hai::Suhai(n, s) = m.tiles[0] {
@@ -789,7 +826,7 @@ fn check_ittsu<'a>(shuntsu: &[&'a Mentsu]) -> bool {
         return false;
     }
 
-    let mut suits: HashMap<Suhai, HashSet<u8>> = HashMap::new();
+    let mut suits: BTreeMap<Suhai, BTreeSet<u8>> = BTreeMap::new();
 
     for m in shuntsu {
         if let Hai::Suhai(n, s) = m.tiles[0] {
@@ -816,7 +853,7 @@ fn check_sanshoku_doukou(hand: &AgariHand) -> bool {
         return false;
     }
 
-    let mut numbers: HashMap<u8, (bool, bool, bool)> = HashMap::new();
+    let mut numbers: BTreeMap<u8, (bool, bool, bool)> = BTreeMap::new();
 
     for m in koutsu {
         if let Hai::Suhai(n, s) = m.tiles[0] {
@@ -832,6 +869,29 @@ fn check_sanshoku_doukou(hand: &AgariHand) -> bool {
     numbers.values().any(|&(m, p, s)| m && p && s)
 }
 
+/// Longest run of same-suit triplets at consecutive numbers (e.g. 222m 333m 444m = 3)
+fn count_renkou(hand: &AgariHand) -> u8 {
+    let triplets: BTreeSet<(Suhai, u8)> = hand
+        .mentsu
+        .iter()
+        .filter(|m| is_koutsu_or_kantsu(m))
+        .filter_map(|m| match m.tiles[0] {
+            Hai::Suhai(n, s) => Some((s, n)),
+            _ => None,
+        })
+        .collect();
+
+    let mut longest = 0;
+    for &(suit, n) in &triplets {
+        let mut run = 1;
+        while triplets.contains(&(suit, n + run)) {
+            run += 1;
+        }
+        longest = longest.max(run);
+    }
+    longest
+}
+
 fn check_shousangen(hand: &AgariHand) -> bool {
     let mut dragon_koutsu = 0;
     let mut dragon_atama = false;
@@ -882,52 +942,41 @@ fn check_chanta_junchan(groups: &[Vec<Hai>]) -> (bool, bool) {
     (is_chanta, is_junchan && is_chanta)
 }
 
-fn check_color(all_tiles: &[Hai]) -> (bool, bool, Option<Suhai>) {
-    let mut suit = None;
-    let mut has_jihai = false;
-    let mut is_honitsu = true;
-    let mut is_chinitsu = true;
-
-    for tile in all_tiles {
-        match tile {
-            Hai::Suhai(_n, s) => {
-                if suit.is_none() {
-                    suit = Some(*s);
-                } else if suit != Some(*s) {
-                    is_honitsu = false;
-                    is_chinitsu = false;
-                    break;
-                }
-            }
-            Hai::Jihai(_) => {
-                has_jihai = true;
-                is_chinitsu = false;
-            }
-        }
-    }
-
-    if !has_jihai && suit.is_none() {
-        is_honitsu = false;
-        is_chinitsu = false;
-    }
+fn check_color(counts: &[u8; 34]) -> (bool, bool, Option<Suhai>) {
+    let suits: Vec<Suhai> = [Suhai::Manzu, Suhai::Pinzu, Suhai::Souzu]
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| counts[i * 9..i * 9 + 9].iter().any(|&c| c > 0))
+        .map(|(_, s)| s)
+        .collect();
+    let has_jihai = counts[27..].iter().any(|&c| c > 0);
 
-    if !has_jihai {
-        is_honitsu = false;
+    // exactly one suit, with (honitsu) or without (chinitsu) honors
+    if suits.len() != 1 {
+        return (false, false, None);
     }
 
-    (is_honitsu, is_chinitsu, suit)
+    (has_jihai, !has_jihai, Some(suits[0]))
 }
 
-fn check_honitsu(all_tiles: &[Hai]) -> (bool, Option<Suhai>) {
-    let (hon, _chin, suit) = check_color(all_tiles);
+fn check_honitsu(counts: &[u8; 34]) -> (bool, Option<Suhai>) {
+    let (hon, _chin, suit) = check_color(counts);
     (hon, suit)
 }
 
-fn check_chinitsu(all_tiles: &[Hai]) -> (bool, Option<Suhai>) {
-    let (_hon, chin, suit) = check_color(all_tiles);
+fn check_chinitsu(counts: &[u8; 34]) -> (bool, Option<Suhai>) {
+    let (_hon, chin, suit) = check_color(counts);
     (chin, suit)
 }
 
+fn tile_counts(tiles: &[Hai]) -> [u8; 34] {
+    let mut counts = [0u8; 34];
+    for tile in tiles {
+        counts[tile_to_index(tile)] += 1;
+    }
+    counts
+}
+
 fn get_all_tiles(hand: &AgariHand) -> Vec<Hai> {
     let mut tiles = Vec::with_capacity(14);
     tiles.push(hand.atama.0);
@@ -997,10 +1046,11 @@ fn count_concealed_koutsu(hand: &AgariHand, agari_type: AgariType) -> u8 {
         }
 
         if m.mentsu_type == MentsuType::Koutsu {
-            if agari_type == AgariType::Ron {
-                if m.tiles[0] == hand.agari_hai {
-                    continue; // This triplet was completed by Ron, not concealed.
-                }
+            if agari_type == AgariType::Ron
+                && hand.machi == Machi::Shanpon
+                && m.tiles[0] == hand.agari_hai
+            {
+                continue; // This triplet was completed by Ron, not concealed.
             }
             count += 1;
         } else if m.mentsu_type == MentsuType::Kantsu {
@@ -1010,14 +1060,383 @@ fn count_concealed_koutsu(hand: &AgariHand, agari_type: AgariType) -> u8 {
     count
 }
 
+// DaburuRiichi replaces Riichi (the GUI sets is_riichi too); Ippatsu stacks with either
+fn riichi_yaku(player: &PlayerContext, rules: &Ruleset) -> Vec<Yaku> {
+    let mut yaku = Vec::new();
+    if player.is_daburu_riichi {
+        yaku.push(Yaku::DaburuRiichi);
+    } else if player.is_riichi {
+        yaku.push(Yaku::Riichi);
+    }
+    if rules.open_riichi && player.is_open_riichi {
+        yaku.push(Yaku::OpenRiichi);
+    }
+    if player.is_ippatsu {
+        yaku.push(Yaku::Ippatsu);
+    }
+    yaku
+}
+
 fn is_koutsu_or_kantsu(mentsu: &Mentsu) -> bool {
     mentsu.mentsu_type == MentsuType::Koutsu || mentsu.mentsu_type == MentsuType::Kantsu
 }
 
 fn is_green_tile(tile: &Hai) -> bool {
-    match tile {
-        Hai::Suhai(n, Suhai::Souzu) => *n == 2 || *n == 3 || *n == 4 || *n == 6 || *n == 8,
-        Hai::Jihai(Jihai::Sangen(Sangenpai::Hatsu)) => true,
-        _ => false,
+    GREEN_MASK[tile_to_index(tile)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_hand, is_chiitoitsu, is_kokushi};
+    use crate::implements::game::SpecialWin;
+    use crate::implements::hand::HandStructure;
+    use crate::implements::input::OpenMeldInput;
+    use crate::implements::rules::Ruleset;
+    use crate::implements::scoring::HandLimit;
+    use crate::implements::test_hands::counts;
+    use crate::implements::test_hands::{dealer_hand, hand, tile, tiles};
+    use crate::implements::yaku::Yaku;
+    use crate::implements::{calculate_agari, calculate_agari_with_rules};
+    use alloc::vec;
+
+    #[test]
+    fn one_red_five_is_one_akadora() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.game_context.num_akadora = 1;
+        let result = calculate_agari(&input).unwrap();
+
+        assert_eq!(result.dora_count(Yaku::AkaDora), 1);
+        assert_eq!(result.num_akadora, 1);
+        // Pinfu + Tanyao + AkaDora
+        assert_eq!(result.han, 3);
+    }
+
+    #[test]
+    fn daichisei_only_under_its_flag() {
+        let mut input = hand("11223344556677z", "7z", false);
+        input.game_context.dora_indicators = tiles("9m");
+
+        let off = calculate_agari(&input).unwrap();
+        assert_eq!(off.yaku_list, vec![Yaku::Tsuuiisou]);
+
+        let rules = Ruleset {
+            daichisei: true,
+            ..Ruleset::default()
+        };
+        let on = calculate_agari_with_rules(&input, &rules).unwrap();
+        assert_eq!(on.yaku_list, vec![Yaku::Daichisei]);
+        assert_eq!(on.limit_name, Some(HandLimit::Yakuman));
+        assert_eq!(on.total_payment, 32000);
+    }
+
+    #[test]
+    fn single_suit_seven_pairs_is_chiitoitsu_and_chinitsu() {
+        let input = hand("11223344557799m", "9m", false);
+        let result = calculate_agari(&input).unwrap();
+        assert_eq!(result.yaku_list, vec![Yaku::Chinitsu, Yaku::Chiitoitsu]);
+        assert_eq!(result.han, 8);
+        assert_eq!(result.fu, 25);
+    }
+
+    #[test]
+    fn seven_pairs_with_honors_is_honitsu() {
+        let input = hand("1133557799m1166z", "6z", false);
+        let result = calculate_agari(&input).unwrap();
+        assert!(result.has_yaku(Yaku::Honitsu));
+        assert!(result.has_yaku(Yaku::Chiitoitsu));
+    }
+
+    #[test]
+    fn classify_hand_covers_all_four_outcomes() {
+        let standard = classify_hand(&hand("234567m345678p44s", "8p", true));
+        assert!(matches!(standard, Ok(HandStructure::YonmentsuIchiatama(_))));
+
+        let chiitoitsu = classify_hand(&hand("1155m2277p3399s11z", "1z", false));
+        assert!(matches!(chiitoitsu, Ok(HandStructure::Chiitoitsu { .. })));
+
+        let kokushi = classify_hand(&hand("119m19p19s1234567z", "1m", false));
+        assert!(matches!(kokushi, Ok(HandStructure::KokushiMusou { .. })));
+
+        // 14 tiles, one off from several shapes
+        let invalid = classify_hand(&hand("1155m2277p3399s12z", "1z", false));
+        assert_eq!(
+            invalid,
+            Err("Invalid hand: cannot form four melds and a pair (one tile off).")
+        );
+    }
+
+    #[test]
+    fn three_mixed_kans_are_sankantsu() {
+        let mut input = hand("234p66z", "4p", false);
+        input.closed_kans = vec![tile("1m"), tile("5s")];
+        input.open_melds = vec![OpenMeldInput::kan(tile("9p"))];
+        input.player_context.is_menzen = false;
+        let result = calculate_agari(&input).unwrap();
+
+        assert_eq!(result.yaku_list, vec![Yaku::Sankantsu]);
+        assert_eq!(result.han, 2);
+    }
+
+    #[test]
+    fn four_mixed_kans_are_suukantsu() {
+        let mut input = hand("66z", "6z", false);
+        input.closed_kans = vec![tile("1m"), tile("5s")];
+        input.open_melds = vec![
+            OpenMeldInput::kan(tile("9p")),
+            OpenMeldInput::kan(tile("2z")),
+        ];
+        input.player_context.is_menzen = false;
+        let result = calculate_agari(&input).unwrap();
+
+        assert_eq!(result.yaku_list, vec![Yaku::Suukantsu]);
+        assert!(result.is_yakuman());
+    }
+
+    #[test]
+    fn sanrenkou_ignores_a_decoy_triplet_and_stacks_with_toitoi() {
+        let mut input = hand("222333444m55s", "5s", false);
+        input.open_melds = vec![OpenMeldInput::pon(tile("7p"))];
+        input.player_context.is_menzen = false;
+        let rules = Ruleset {
+            sanrenkou: true,
+            ..Ruleset::default()
+        };
+
+        let result = calculate_agari_with_rules(&input, &rules).unwrap();
+        assert!(result.has_yaku(Yaku::Sanrenkou));
+        assert!(result.has_yaku(Yaku::Toitoi));
+        assert!(result.has_yaku(Yaku::Sanankou));
+
+        let result = calculate_agari(&input).unwrap();
+        assert!(!result.has_yaku(Yaku::Sanrenkou));
+    }
+
+    #[test]
+    fn suurenkou_needs_all_four_triplets_consecutive() {
+        let rules = Ruleset {
+            sanrenkou: true,
+            suurenkou: true,
+            ..Ruleset::default()
+        };
+        let mut input = hand("222333444m77p", "7p", false);
+        input.player_context.is_menzen = false;
+
+        input.open_melds = vec![OpenMeldInput::pon(tile("5m"))];
+        let result = calculate_agari_with_rules(&input, &rules).unwrap();
+        assert_eq!(result.yaku_list, vec![Yaku::Suurenkou]);
+
+        // 666m leaves a gap, so only the first three count
+        input.open_melds = vec![OpenMeldInput::pon(tile("6m"))];
+        let result = calculate_agari_with_rules(&input, &rules).unwrap();
+        assert!(result.has_yaku(Yaku::Sanrenkou));
+        assert!(!result.has_yaku(Yaku::Suurenkou));
+    }
+
+    #[test]
+    fn open_hand_with_only_dora_has_no_yaku() {
+        let mut input = hand("456p789p234s55s", "4s", false);
+        input.open_melds = vec![OpenMeldInput::chi(tile("1m"))];
+        input.player_context.is_menzen = false;
+        input.game_context.dora_indicators = tiles("4s");
+        assert_eq!(
+            calculate_agari(&input).err(),
+            Some("No Yaku Found: dora alone cannot win.")
+        );
+
+        // Houtei is a yaku of its own, so the same hand can win on the last discard
+        input.game_context.special_win = SpecialWin::Houtei;
+        let result = calculate_agari(&input).unwrap();
+        assert_eq!(
+            result.yaku_list,
+            vec![Yaku::HouteiRaoyui, Yaku::Dora, Yaku::Dora]
+        );
+    }
+
+    #[test]
+    fn menzen_tsumo_only_for_a_concealed_tsumo() {
+        let tsumo = calculate_agari(&hand("234567m345678p44s", "8p", true)).unwrap();
+        assert!(tsumo.has_yaku(Yaku::MenzenTsumo));
+        assert!(tsumo.has_yaku(Yaku::Pinfu));
+        assert_eq!(tsumo.fu, 20);
+
+        let ron = calculate_agari(&hand("234567m345678p44s", "8p", false)).unwrap();
+        assert!(!ron.has_yaku(Yaku::MenzenTsumo));
+
+        let mut open = hand("567m345678p44s", "8p", true);
+        open.open_melds = vec![OpenMeldInput::chi(tile("2m"))];
+        open.player_context.is_menzen = false;
+        let open = calculate_agari(&open).unwrap();
+        assert!(!open.has_yaku(Yaku::MenzenTsumo));
+        assert_eq!(open.yaku_list, vec![Yaku::Tanyao]);
+    }
+
+    #[test]
+    fn an_ankan_blocks_pinfu() {
+        // all runs, ryanmen wait, plain pair: only the kan stands in the way
+        let mut input = hand("234567p23488s", "4s", true);
+        input.closed_kans = vec![tile("2m")];
+        let result = calculate_agari(&input).unwrap();
+
+        assert!(!result.has_yaku(Yaku::Pinfu));
+        assert_eq!(result.yaku_list, vec![Yaku::MenzenTsumo, Yaku::Tanyao]);
+        // 20 base + 2 tsumo + 16 concealed simple kan = 38, rounded up
+        assert_eq!(result.fu, 40);
+    }
+
+    #[test]
+    fn open_riichi_adds_a_han_under_its_flag() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.player_context.is_riichi = true;
+        input.player_context.is_open_riichi = true;
+        let rules = Ruleset {
+            open_riichi: true,
+            ..Ruleset::default()
+        };
+
+        let with_flag = calculate_agari_with_rules(&input, &rules).unwrap();
+        let without = calculate_agari(&input).unwrap();
+        assert!(with_flag.has_yaku(Yaku::OpenRiichi));
+        assert!(!without.has_yaku(Yaku::OpenRiichi));
+        assert_eq!(with_flag.han, without.han + 1);
+
+        input.player_context.is_riichi = false;
+        assert_eq!(
+            calculate_agari_with_rules(&input, &rules).err(),
+            Some("Invalid game state: Open Riichi requires a Riichi declaration.")
+        );
+    }
+
+    #[test]
+    fn ryanpeikou_is_chosen_over_chiitoitsu() {
+        // also seven pairs: 22 33 44m 22 33 44p 55s
+        let result = calculate_agari(&hand("223344m223344p55s", "5s", false)).unwrap();
+        assert!(result.has_yaku(Yaku::Ryanpeikou));
+        assert!(!result.has_yaku(Yaku::Iipeikou));
+        assert!(!result.has_yaku(Yaku::Chiitoitsu));
+        assert_eq!(result.yaku_list, vec![Yaku::Ryanpeikou, Yaku::Tanyao]);
+    }
+
+    #[test]
+    fn shousangen_keeps_both_dragon_triplets() {
+        // haku and hatsu triplets, chun as the pair
+        let mut input = hand("234m567p55566677z", "4m", false);
+        input.game_context.dora_indicators = tiles("9s");
+        let result = calculate_agari(&input).unwrap();
+        assert!(result.has_yaku(Yaku::Shousangen));
+        let yakuhai = result
+            .yaku_list
+            .iter()
+            .filter(|y| **y == Yaku::YakuhaiSangenpai)
+            .count();
+        assert_eq!(yakuhai, 2);
+        assert_eq!(result.han, 2 + 1 + 1);
+    }
+
+    #[test]
+    fn double_riichi_replaces_riichi_and_stacks_with_ippatsu() {
+        let mut input = hand("234567m345678p44s", "8p", false);
+        input.player_context.is_daburu_riichi = true;
+        input.player_context.is_ippatsu = true;
+        let result = calculate_agari(&input).unwrap();
+
+        assert!(result.has_yaku(Yaku::DaburuRiichi));
+        assert!(!result.has_yaku(Yaku::Riichi));
+        assert!(result.has_yaku(Yaku::Ippatsu));
+        // 2 + 1 + Pinfu + Tanyao
+        assert_eq!(result.han, 5);
+    }
+
+    #[test]
+    fn tenhou_is_exactly_one_yakuman() {
+        let mut input = dealer_hand("234567m345678p44s", "8p", true);
+        input.game_context.special_win = SpecialWin::Tenhou;
+        input.game_context.dora_indicators = tiles("3s");
+        let result = calculate_agari(&input).unwrap();
+
+        // no MenzenTsumo, Pinfu, Tanyao or dora next to the yakuman
+        assert_eq!(result.yaku_list, vec![Yaku::Tenhou]);
+        assert_eq!(result.han, 13);
+        assert_eq!(result.total_payment, 48000);
+    }
+
+    #[test]
+    fn seven_pairs_and_kokushi_are_told_from_standard_hands() {
+        assert!(is_chiitoitsu(&counts("2233m4455p667788s")));
+        // standard hands with pairs in them
+        assert!(!is_chiitoitsu(&counts("234567m345678p44s")));
+        assert!(!is_chiitoitsu(&counts("112233m456789p77s")));
+        // four of a kind is not two pairs
+        assert!(!is_chiitoitsu(&counts("2222m4455p667788s")));
+
+        assert!(is_kokushi(&counts("119m19p19s1234567z"), tile("1m")));
+        assert!(!is_kokushi(&counts("119m19p19s1234567z"), tile("2m")));
+        assert!(!is_kokushi(&counts("111m19p19s123456z9m"), tile("1m")));
+        assert!(!is_kokushi(&counts("234567m345678p44s"), tile("8p")));
+    }
+
+    #[test]
+    fn chuuren_shape_with_a_called_tile_is_not_chuuren() {
+        let closed = calculate_agari(&hand("11123455678999m", "5m", true)).unwrap();
+        assert_eq!(closed.yaku_list, vec![Yaku::JunseiChuurenPoutou]);
+
+        // same 14 tiles, but 234m was called
+        let mut input = hand("11155678999m", "5m", true);
+        input.open_melds = vec![OpenMeldInput::chi(tile("2m"))];
+        input.player_context.is_menzen = false;
+        let open = calculate_agari(&input).unwrap();
+        assert!(!open.has_yaku(Yaku::ChuurenPoutou));
+        assert!(!open.has_yaku(Yaku::JunseiChuurenPoutou));
+        assert!(open.has_yaku(Yaku::Chinitsu));
+        assert!(!open.is_yakuman());
+    }
+
+    #[test]
+    fn require_yaku_off_lets_dora_alone_score() {
+        let mut input = hand("456p789p234s55s", "4s", false);
+        input.open_melds = vec![OpenMeldInput::chi(tile("1m"))];
+        input.player_context.is_menzen = false;
+        input.game_context.dora_indicators = tiles("4s");
+        assert!(calculate_agari(&input).is_err());
+
+        let house = Ruleset {
+            require_yaku: false,
+            ..Ruleset::default()
+        };
+        let result = calculate_agari_with_rules(&input, &house).unwrap();
+        assert_eq!(result.yaku_list, vec![Yaku::Dora, Yaku::Dora]);
+        assert_eq!(result.han, 2);
+
+        // still no win with neither yaku nor dora
+        input.game_context.dora_indicators = tiles("7z");
+        assert_eq!(
+            calculate_agari_with_rules(&input, &house).err(),
+            Some("No Yaku Found: no yaku and no dora.")
+        );
+    }
+
+    #[test]
+    fn terminal_yaku_are_exclusive() {
+        let yaku =
+            |all: &str, win: &str| calculate_agari(&hand(all, win, false)).unwrap().yaku_list;
+
+        // East triplet: chanta, not junchan
+        let chanta = yaku("123m789p789s111z99s", "3m");
+        assert!(chanta.contains(&Yaku::Chanta));
+        assert!(!chanta.contains(&Yaku::Junchan));
+
+        // no honors: junchan only
+        let junchan = yaku("123m789p789s111s99s", "3m");
+        assert!(junchan.contains(&Yaku::Junchan));
+        assert!(!junchan.contains(&Yaku::Chanta));
+
+        // no runs: honroutou with toitoi or chiitoitsu, never chanta
+        let toitoi = yaku("111m999p111s99s222z", "2z");
+        assert!(toitoi.contains(&Yaku::Honroutou));
+        assert!(toitoi.contains(&Yaku::Toitoi));
+        assert!(!toitoi.contains(&Yaku::Chanta));
+        let pairs = yaku("1199m1199p1199s11z", "1z");
+        assert!(pairs.contains(&Yaku::Honroutou));
+        assert!(pairs.contains(&Yaku::Chiitoitsu));
+        assert!(!pairs.contains(&Yaku::Chanta));
     }
 }
@@ -0,0 +1,8 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("the `alloc` feature is required (it is enabled by `std`)");
+
+extern crate alloc;
+
+pub mod implements;
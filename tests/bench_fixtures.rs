@@ -0,0 +1,28 @@
+// bench_fixtures.rs: Checks that the benchmark fixtures are the hands they claim to be
+
+#[path = "../benches/fixtures/mod.rs"]
+mod fixtures;
+
+use riichi_mahjong_scoring_calculator::implements::{
+    calculate_agari, parse_tile, shanten, tiles::tile_to_index, waits,
+};
+
+#[test]
+fn every_fixture_is_a_scoring_hand() {
+    for (name, input) in fixtures::all_hands() {
+        assert!(calculate_agari(&input).is_ok(), "{name}");
+    }
+}
+
+#[test]
+fn every_fixture_is_tenpai_on_its_winning_tile() {
+    for &(name, tiles, win, _) in fixtures::HANDS {
+        let counts = fixtures::tenpai_counts(tiles, win);
+        assert_eq!(shanten(&counts), 0, "{name}");
+        let win = tile_to_index(&parse_tile(win).unwrap());
+        assert!(
+            waits(&counts).iter().any(|t| tile_to_index(t) == win),
+            "{name}"
+        );
+    }
+}